@@ -16,24 +16,42 @@
 
 
 use std::ptr;
-use std::rc::Rc;
 use std::ffi::CString;
+use std::borrow::Cow;
+use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::os::raw::{c_int, c_uint, c_void};
+use std::slice;
 
 use ::freetype_sys::*;
-use ::harfbuzz::{HBFace, FTFontRef};
+use ::harfbuzz::{HBFace, FaceOwner, FTFontRef};
+use ::error::{self, Error};
+use ::outline::{Outline, OutlineCommand};
+use ::raster::{Antialias, GlyphBitmap, Hinting, RasterOptions};
 
 pub struct Freetype {
     ptr: *mut FT_LibraryRec_,
+    lock: Mutex<()>,
 }
 
+// FreeType requires creating and destroying faces of a library to be
+// serialised. Both go through `lock`, so the library can be shared through an
+// `Arc` by faces living on different threads.
+unsafe impl Send for Freetype {}
+unsafe impl Sync for Freetype {}
+
 impl Freetype {
-    pub fn new() -> Freetype {
+    pub fn new() -> Result<Freetype, Error> {
         let mut library = ptr::null_mut();
         let error = unsafe { FT_Init_FreeType(&mut library) };
         if error != FT_Err_Ok as i32 {
-            panic!("Failed to initialize freetype library. Error: {:?}", error);
+            return Err(Error::FreeType(error));
         }
-        Freetype { ptr: library }
+        Ok(Freetype { ptr: library, lock: Mutex::new(()) })
+    }
+
+    fn lock(&self) -> MutexGuard<()> {
+        self.lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 }
 
@@ -45,41 +63,358 @@ impl Drop for Freetype {
 
 
 pub struct FreetypeFace {
-    library: Rc<Freetype>,
     ptr: *mut FT_FaceRec_,
-    hb_face: HBFace,
+    // Dropped by hand before the FT_Face reference of this struct is released
+    hb_face: ManuallyDrop<HBFace>,
+    library: Arc<Freetype>,
+}
+
+// Kept alive by `hb_face` and every face derived from it, since they hold a
+// reference on the FT_Face. FreeType reads from `data`, the backing buffer of
+// faces opened with `new_from_memory`, for as long as the face lives. The last
+// of these faces to be dropped runs FT_Done_Face, so they are released under
+// the library lock.
+struct FaceResources {
+    _data: Option<Cow<'static, [u8]>>,
+    library: Arc<Freetype>,
 }
 
+impl FaceOwner for FaceResources {
+    fn lock(&self) -> MutexGuard<()> {
+        self.library.lock()
+    }
+}
+
+// All access to the FT_Face goes through the lock of `hb_face`, see `HBFace`.
+unsafe impl Send for FreetypeFace {}
+unsafe impl Sync for FreetypeFace {}
+
 impl FreetypeFace {
-    pub fn new_from_file(library: Rc<Freetype>, path: &str, index: u32) -> Result<FreetypeFace, ()> {
+    pub fn new_from_file(library: Arc<Freetype>, path: &str, index: u32) -> Result<FreetypeFace, Error> {
+        let mut ptr = ptr::null_mut();
+        let path = CString::new(path).map_err(|_| Error::InvalidPath(path.to_string()))?;
+
+        let error = {
+            let _lock = library.lock();
+            unsafe { FT_New_Face(library.ptr, path.as_ptr(), index as i64, &mut ptr) }
+        };
+
+        FreetypeFace::new(library, error, ptr, index, None)
+    }
+
+    /// Opens a face from font data held in memory, for example a font embedded
+    /// with `include_bytes!` or downloaded into a `Vec<u8>`. The buffer is kept
+    /// alive for the lifetime of the returned face.
+    pub fn new_from_memory<D>(library: Arc<Freetype>, data: D, index: u32) -> Result<FreetypeFace, Error>
+        where D: Into<Cow<'static, [u8]>> {
+        let data = data.into();
         let mut ptr = ptr::null_mut();
-        let path = CString::new(path).unwrap();
 
-        let error = unsafe { FT_New_Face(library.ptr, path.as_ptr(),
-                                         index as i64, &mut ptr) };
+        let error = {
+            let _lock = library.lock();
+            unsafe { FT_New_Memory_Face(library.ptr, data.as_ptr(), data.len() as i64,
+                                        index as i64, &mut ptr) }
+        };
+
+        FreetypeFace::new(library, error, ptr, index, Some(data))
+    }
 
+    fn new(library: Arc<Freetype>, error: FT_Error, ptr: *mut FT_FaceRec_, index: u32,
+           data: Option<Cow<'static, [u8]>>) -> Result<FreetypeFace, Error> {
         if error != FT_Err_Ok as i32 {
-            return Err(());
+            // FreeType reports an out of range face index as an invalid argument
+            if error == error::FT_ERR_INVALID_ARGUMENT && index > 0 {
+                return Err(Error::InvalidFaceIndex(index));
+            }
+            return Err(Error::from_ft_error(error));
+        }
+
+        // Start with a size of upem/64 pixels per em so that FreeType's 26.6
+        // values, and therefore all HBFace metrics, are in font units. Bitmap
+        // only faces cannot be scaled and keep the strike FreeType selected.
+        let scalable = unsafe { (*ptr).face_flags } & FT_FACE_FLAG_SCALABLE != 0;
+        if scalable {
+            let upem = unsafe { (*ptr).units_per_EM } as i64;
+            let error = unsafe { FT_Set_Char_Size(ptr, 0, upem, 72, 72) };
+            if error != FT_Err_Ok as i32 {
+                let _lock = library.lock();
+                unsafe { FT_Done_Face(ptr) };
+                return Err(Error::from_ft_error(error));
+            }
+        }
+
+        let mut hb_face = match HBFace::from_freetype_font(ptr as FTFontRef) {
+            Ok(hb_face) => hb_face,
+            Err(err) => {
+                let _lock = library.lock();
+                unsafe { FT_Done_Face(ptr) };
+                return Err(err);
+            }
+        };
+
+        hb_face.set_owner(Arc::new(FaceResources { _data: data, library: library.clone() }));
+        if !scalable {
+            let strike_ppem = unsafe {
+                let size = (*ptr).size;
+                if size.is_null() { 0 } else { (*size).metrics.y_ppem }
+            };
+            if strike_ppem > 0 {
+                hb_face.freetype_size_changed(strike_ppem as f32);
+            }
         }
 
         Ok(FreetypeFace {
-            library,
             ptr,
-            hb_face: HBFace::from_freetype_font(ptr as FTFontRef)
+            hb_face: ManuallyDrop::new(hb_face),
+            library,
         })
     }
 
-    pub fn set_size_pixels(&mut self, width: u32, height: u32) {
-        unsafe { FT_Set_Pixel_Sizes(self.ptr, width, height) };
+    pub fn set_size_pixels(&mut self, width: u32, height: u32) -> Result<(), Error> {
+        let error = {
+            let _lock = self.hb_face.lock();
+            unsafe { FT_Set_Pixel_Sizes(self.ptr, width, height) }
+        };
+        if error != FT_Err_Ok as i32 {
+            return Err(Error::from_ft_error(error));
+        }
+        let ppem = if height == 0 { width } else { height };
+        self.hb_face.freetype_size_changed(ppem as f32);
+        Ok(())
+    }
+
+    /// Outline of `glyph_index` in the units of the face: font units until a
+    /// size is set, 1/64 pixels afterwards. The outline is not hinted. Glyphs
+    /// without an outline, like spaces or bitmap glyphs, give an empty outline.
+    pub fn glyph_outline(&self, glyph_index: u32) -> Result<Outline, Error> {
+        let _lock = self.hb_face.lock();
+        let error = unsafe { FT_Load_Glyph(self.ptr, glyph_index, OUTLINE_LOAD_FLAGS) };
+        if error != FT_Err_Ok as i32 {
+            return Err(Error::from_ft_error(error));
+        }
+
+        let funcs = OutlineFuncs {
+            move_to: outline_move_to,
+            line_to: outline_line_to,
+            conic_to: outline_conic_to,
+            cubic_to: outline_cubic_to,
+            shift: 0,
+            delta: 0,
+        };
+        let mut commands: Vec<OutlineCommand> = Vec::new();
+        let error = unsafe {
+            let outline = &mut (*(*self.ptr).glyph).outline as *mut FT_Outline;
+            FT_Outline_Decompose(outline, &funcs, &mut commands as *mut _ as *mut c_void)
+        };
+        if error != FT_Err_Ok as i32 {
+            return Err(Error::from_ft_error(error));
+        }
+
+        if !commands.is_empty() {
+            commands.push(OutlineCommand::Close);
+        }
+        Ok(Outline::new(commands))
+    }
+
+    /// Renders `glyph_index` at the current size. `subpixel_offset` moves the
+    /// outline by a fraction of a pixel, right and up, before rendering, so
+    /// glyphs can be drawn at fractional pen positions. The antialias mode of
+    /// the result is the one FreeType rendered, which is `Mono` for embedded
+    /// one bit bitmaps whatever was asked for. Embedded bitmaps in other
+    /// formats, like gray levels with 2 or 4 bits or color, are not supported.
+    pub fn rasterize(&self, glyph_index: u32, subpixel_offset: (f32, f32),
+                     options: &RasterOptions) -> Result<GlyphBitmap, Error> {
+        self.rasterize_scaled(glyph_index, 1., subpixel_offset, options)
+    }
+
+    /// Renders `glyph_index` at `scale` times the current size, for example
+    /// the glyphs of a face from `HBFace::font_for_style`. The outline is
+    /// hinted at the current size and scaled afterwards.
+    pub fn rasterize_scaled(&self, glyph_index: u32, scale: f32, subpixel_offset: (f32, f32),
+                            options: &RasterOptions) -> Result<GlyphBitmap, Error> {
+        let (target, render_mode) = match options.get_antialias() {
+            Antialias::Mono => (FT_RENDER_MODE_MONO, FT_RENDER_MODE_MONO),
+            Antialias::Gray if options.get_hinting() == Hinting::Light => (FT_RENDER_MODE_LIGHT, FT_RENDER_MODE_NORMAL),
+            Antialias::Gray => (FT_RENDER_MODE_NORMAL, FT_RENDER_MODE_NORMAL),
+            Antialias::Lcd if options.get_hinting() == Hinting::Light => (FT_RENDER_MODE_LIGHT, FT_RENDER_MODE_LCD),
+            Antialias::Lcd => (FT_RENDER_MODE_LCD, FT_RENDER_MODE_LCD),
+        };
+        let mut load_flags = ((target & 15) << 16) as i32;
+        if options.get_hinting() == Hinting::None {
+            load_flags |= FT_LOAD_NO_HINTING;
+        }
+
+        let _lock = self.hb_face.lock();
+        let error = if scale != 1. {
+            let fixed = (scale * 65536.).round() as FT_Fixed;
+            let matrix = Matrix { xx: fixed, xy: 0, yx: 0, yy: fixed };
+            // The transform is state of the FT_Face, reset it before unlocking
+            unsafe {
+                FT_Set_Transform(self.ptr, &matrix, ptr::null());
+                let error = FT_Load_Glyph(self.ptr, glyph_index, load_flags);
+                FT_Set_Transform(self.ptr, ptr::null(), ptr::null());
+                error
+            }
+        } else {
+            unsafe { FT_Load_Glyph(self.ptr, glyph_index, load_flags) }
+        };
+        if error != FT_Err_Ok as i32 {
+            return Err(Error::from_ft_error(error));
+        }
+
+        let slot = unsafe { (*self.ptr).glyph };
+        let (dx, dy) = subpixel_offset;
+        if dx != 0. || dy != 0. {
+            unsafe {
+                FT_Outline_Translate(&mut (*slot).outline, (dx * 64.).round() as FT_Pos,
+                                     (dy * 64.).round() as FT_Pos)
+            };
+        }
+
+        let error = unsafe { FT_Render_Glyph(slot, render_mode) };
+        if error != FT_Err_Ok as i32 {
+            return Err(Error::from_ft_error(error));
+        }
+
+        let bitmap = unsafe { &(*slot).bitmap };
+        let antialias = match bitmap.pixel_mode {
+            FT_PIXEL_MODE_MONO => Antialias::Mono,
+            FT_PIXEL_MODE_GRAY => Antialias::Gray,
+            FT_PIXEL_MODE_LCD => Antialias::Lcd,
+            pixel_mode => return Err(Error::UnsupportedPixelMode(pixel_mode)),
+        };
+        let rows = bitmap.rows as usize;
+        let source_pitch = (bitmap.pitch as i64).abs() as usize;
+        let source: &[u8] = if rows == 0 || bitmap.buffer.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(bitmap.buffer, rows * source_pitch) }
+        };
+
+        // FreeType stores bitmaps upwards when the pitch is negative
+        let source_row = |index: usize| {
+            let index = if bitmap.pitch < 0 { rows - 1 - index } else { index };
+            &source[index * source_pitch..(index + 1) * source_pitch]
+        };
+
+        let (width, pitch) = match antialias {
+            Antialias::Lcd => (bitmap.width / 3, bitmap.width as usize),
+            _ => (bitmap.width, bitmap.width as usize),
+        };
+        let mut data = Vec::with_capacity(pitch * rows);
+        for index in 0..rows {
+            let row = source_row(index);
+            if antialias == Antialias::Mono {
+                data.extend((0..pitch).map(|x| if row[x / 8] & (0x80 >> (x % 8)) != 0 { 255 } else { 0 }));
+            } else {
+                data.extend_from_slice(&row[..pitch]);
+            }
+        }
+
+        let (left, top) = unsafe { ((*slot).bitmap_left, (*slot).bitmap_top) };
+        Ok(GlyphBitmap::new(width, rows as u32, pitch, left, top, antialias, data))
     }
 
     pub fn get_hb_face(&self) -> &HBFace {
         &self.hb_face
     }
+
+    pub fn get_hb_face_mut(&mut self) -> &mut HBFace {
+        &mut self.hb_face
+    }
 }
 
 impl Drop for FreetypeFace {
     fn drop(&mut self) {
+        // Locks the library itself, through its owner
+        unsafe { ManuallyDrop::drop(&mut self.hb_face) };
+        let _lock = self.library.lock();
         unsafe { FT_Done_Face(self.ptr) };
     }
-}
\ No newline at end of file
+}
+
+const FT_FACE_FLAG_SCALABLE: FT_Long = 0x1;
+
+const FT_LOAD_NO_HINTING: i32 = 0x2;
+const FT_LOAD_NO_BITMAP: i32 = 0x8;
+const OUTLINE_LOAD_FLAGS: i32 = FT_LOAD_NO_HINTING | FT_LOAD_NO_BITMAP;
+
+// FT_Render_Mode values, also used for the FT_LOAD_TARGET_XXX load flags
+const FT_RENDER_MODE_NORMAL: c_uint = 0;
+const FT_RENDER_MODE_LIGHT: c_uint = 1;
+const FT_RENDER_MODE_MONO: c_uint = 2;
+const FT_RENDER_MODE_LCD: c_uint = 3;
+
+// FT_Pixel_Mode values
+const FT_PIXEL_MODE_MONO: u8 = 1;
+const FT_PIXEL_MODE_GRAY: u8 = 2;
+const FT_PIXEL_MODE_LCD: u8 = 5;
+
+#[repr(C)]
+struct Matrix {
+    xx: FT_Fixed,
+    xy: FT_Fixed,
+    yx: FT_Fixed,
+    yy: FT_Fixed,
+}
+
+#[repr(C)]
+struct OutlineFuncs {
+    move_to: extern "C" fn(*const FT_Vector, *mut c_void) -> c_int,
+    line_to: extern "C" fn(*const FT_Vector, *mut c_void) -> c_int,
+    conic_to: extern "C" fn(*const FT_Vector, *const FT_Vector, *mut c_void) -> c_int,
+    cubic_to: extern "C" fn(*const FT_Vector, *const FT_Vector, *const FT_Vector, *mut c_void) -> c_int,
+    shift: c_int,
+    delta: FT_Pos,
+}
+
+// The callbacks receive the command list passed to FT_Outline_Decompose
+
+fn outline_commands<'a>(user: *mut c_void) -> &'a mut Vec<OutlineCommand> {
+    unsafe { &mut *(user as *mut Vec<OutlineCommand>) }
+}
+
+fn point(vector: *const FT_Vector) -> (i32, i32) {
+    let vector = unsafe { &*vector };
+    (vector.x as i32, vector.y as i32)
+}
+
+extern "C" fn outline_move_to(to: *const FT_Vector, user: *mut c_void) -> c_int {
+    let commands = outline_commands(user);
+    // FreeType does not report the end of a contour, it starts the next one
+    if !commands.is_empty() {
+        commands.push(OutlineCommand::Close);
+    }
+    let (x, y) = point(to);
+    commands.push(OutlineCommand::MoveTo(x, y));
+    0
+}
+
+extern "C" fn outline_line_to(to: *const FT_Vector, user: *mut c_void) -> c_int {
+    let (x, y) = point(to);
+    outline_commands(user).push(OutlineCommand::LineTo(x, y));
+    0
+}
+
+extern "C" fn outline_conic_to(control: *const FT_Vector, to: *const FT_Vector, user: *mut c_void) -> c_int {
+    let (cx, cy) = point(control);
+    let (x, y) = point(to);
+    outline_commands(user).push(OutlineCommand::QuadTo(cx, cy, x, y));
+    0
+}
+
+extern "C" fn outline_cubic_to(control1: *const FT_Vector, control2: *const FT_Vector, to: *const FT_Vector,
+                               user: *mut c_void) -> c_int {
+    let (c1x, c1y) = point(control1);
+    let (c2x, c2y) = point(control2);
+    let (x, y) = point(to);
+    outline_commands(user).push(OutlineCommand::CubicTo(c1x, c1y, c2x, c2y, x, y));
+    0
+}
+
+extern {
+    fn FT_Set_Transform(face: *mut FT_FaceRec_, matrix: *const Matrix, delta: *const FT_Vector);
+    fn FT_Outline_Translate(outline: *mut FT_Outline, x_offset: FT_Pos, y_offset: FT_Pos);
+    fn FT_Render_Glyph(slot: FT_GlyphSlot, render_mode: c_uint) -> FT_Error;
+    fn FT_Outline_Decompose(outline: *mut FT_Outline, funcs: *const OutlineFuncs, user: *mut c_void) -> FT_Error;
+}
@@ -15,23 +15,63 @@
 */
 
 
-use std::ffi::{CStr, CString};
+use std::ffi::CStr;
+#[cfg(feature = "harfbuzz-2-6")]
+use std::os::raw::c_char;
 use std::ptr;
-use std::cmp::PartialEq;
+use std::cmp::{self, PartialEq};
 use std::slice;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use ::harfbuzz_sys;
+use ::freetype_sys::{FT_Face, FT_F26Dot6, FT_Err_Ok, FT_Set_Char_Size};
+use ::error::Error;
+use ::math::constants::MathConstants;
+use ::math::style::MathStyle;
+use ::math::variant::MathVariant;
+use ::shaping::{Feature, ShapeOptions, ShapeText};
+#[cfg(feature = "harfbuzz-2-6")]
+use ::shaping::tag;
 
 pub fn hb_version_string() -> String {
     let version = unsafe { CStr::from_ptr(harfbuzz_sys::hb_version_string()) };
     String::from(version.to_str().expect("Harbuzz not linked"))
 }
 
+/// A HarfBuzz face and font pair.
+///
+/// Metrics are returned in the scale of the font. Faces start out in font
+/// units, unless they wrap a FreeType face that already has a size, and switch
+/// to 1/64 pixel units once a size is set with `set_ppem` or
+/// `set_size_points`. `font_units` gives a face that always reports font units.
 pub struct HBFace {
     face: *mut harfbuzz_sys::hb_face_t,
     font: *mut harfbuzz_sys::hb_font_t,
+    ft_face: Option<FTFontRef>,
+    ppem: Option<f32>,
+    // Value of the `ssty` feature applied when shaping, 0 to leave it off
+    script_level: u8,
+    // MATH constants at the current size, refreshed by `set_hb_ppem`
+    constants: MathConstants,
+    lock: Arc<Mutex<()>>,
+    // Resources the font data depends on, shared with derived faces
+    owner: Option<Arc<dyn FaceOwner>>,
 }
 
+/// Keeps the font data of a face and of the faces derived from it alive.
+/// Whichever face is dropped last frees the font, so every face releases its
+/// HarfBuzz objects while holding `lock`.
+pub(crate) trait FaceOwner: Send + Sync {
+    fn lock(&self) -> MutexGuard<()>;
+}
+
+// hb_face_t and hb_font_t are reference counted and safe to move between
+// threads. The FT_Face behind hb-ft fonts is not safe for concurrent use, so
+// every call into HarfBuzz or FreeType holds `lock`, which is shared by all
+// faces derived from the same font.
+unsafe impl Send for HBFace {}
+unsafe impl Sync for HBFace {}
+
 #[cfg(any(target_os="ios", target_os="macos"))]
 pub enum CGFont {}
 #[cfg(any(target_os="ios", target_os="macos"))]
@@ -67,6 +107,7 @@ impl HBGlyphVariant {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HBDirection {
     LTR,
     RTL,
@@ -75,6 +116,13 @@ pub enum HBDirection {
 }
 
 impl HBDirection {
+    pub fn is_vertical(&self) -> bool {
+        match *self {
+            HBDirection::TTB | HBDirection::BTT => true,
+            HBDirection::LTR | HBDirection::RTL => false,
+        }
+    }
+
     fn to_hb_dir(&self) -> harfbuzz_sys::hb_direction_t {
         match *self {
             HBDirection::LTR => harfbuzz_sys::hb_direction_t::HB_DIRECTION_LTR,
@@ -102,6 +150,7 @@ impl<'a> Iterator for HBGlyphVariantIter<'a> {
         }
 
         let mut variants: [harfbuzz_sys::hb_ot_math_glyph_variant_t; 1] = [harfbuzz_sys::hb_ot_math_glyph_variant_t { glyph: 0, advance: 0 }];
+        let _lock = self.face.lock();
         unsafe {
             harfbuzz_sys::hb_ot_math_get_glyph_variants(
                 self.face.font,
@@ -120,6 +169,7 @@ impl<'a> Iterator for HBGlyphVariantIter<'a> {
 
 impl<'a> HBGlyphVariantIter<'a> {
     fn new(face: &'a HBFace, glyph_index: u32, direction: HBDirection) -> HBGlyphVariantIter<'a> {
+        let _lock = face.lock();
         let num_variants = unsafe {
             harfbuzz_sys::hb_ot_math_get_glyph_variants(
                 face.font,
@@ -195,6 +245,7 @@ impl HBGlyphAssembly {
 
 #[derive(Debug)]
 pub struct HBGlyphPosition {
+    glyph_index: u32,
     x_advance: i32,
     y_advance: i32,
     x_offset: i32,
@@ -202,8 +253,9 @@ pub struct HBGlyphPosition {
 }
 
 impl HBGlyphPosition {
-    fn new(data: &harfbuzz_sys::hb_glyph_position_t) -> HBGlyphPosition {
+    fn new(glyph_index: u32, data: &harfbuzz_sys::hb_glyph_position_t) -> HBGlyphPosition {
         HBGlyphPosition {
+            glyph_index,
             x_advance: data.x_advance,
             y_advance: data.y_advance,
             x_offset: data.x_offset,
@@ -211,6 +263,10 @@ impl HBGlyphPosition {
         }
     }
 
+    pub fn glyph_index(&self) -> u32 {
+        self.glyph_index
+    }
+
     pub fn x_advance(&self) -> i32 {
         self.x_advance
     }
@@ -228,24 +284,180 @@ impl HBGlyphPosition {
     }
 }
 
+/// Ink bounding box of a glyph or a run of glyphs. Follows the HarfBuzz
+/// convention: `y_bearing` is the top of the box and `height` is negative for
+/// boxes extending downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HBGlyphExtents {
+    x_bearing: i32,
+    y_bearing: i32,
+    width: i32,
+    height: i32,
+}
+
+impl HBGlyphExtents {
+    fn new(data: &harfbuzz_sys::hb_glyph_extents_t) -> HBGlyphExtents {
+        HBGlyphExtents {
+            x_bearing: data.x_bearing,
+            y_bearing: data.y_bearing,
+            width: data.width,
+            height: data.height,
+        }
+    }
+
+    pub fn x_bearing(&self) -> i32 {
+        self.x_bearing
+    }
+
+    pub fn y_bearing(&self) -> i32 {
+        self.y_bearing
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Distance from the baseline to the top of the ink.
+    pub fn ascent(&self) -> i32 {
+        self.y_bearing
+    }
+
+    /// Distance from the baseline to the bottom of the ink, positive below the
+    /// baseline.
+    pub fn descent(&self) -> i32 {
+        -(self.y_bearing + self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Smallest box containing both `self` and `other` moved by `(dx, dy)`.
+    fn union(&self, other: &HBGlyphExtents, dx: i32, dy: i32) -> HBGlyphExtents {
+        if other.is_empty() {
+            return *self;
+        }
+
+        let left = other.x_bearing + dx;
+        let right = left + other.width;
+        let top = other.y_bearing + dy;
+        let bottom = top + other.height;
+        if self.is_empty() {
+            return HBGlyphExtents { x_bearing: left, y_bearing: top, width: right - left, height: bottom - top };
+        }
+
+        let left = cmp::min(left, self.x_bearing);
+        let right = cmp::max(right, self.x_bearing + self.width);
+        let top = cmp::max(top, self.y_bearing);
+        let bottom = cmp::min(bottom, self.y_bearing + self.height);
+        HBGlyphExtents { x_bearing: left, y_bearing: top, width: right - left, height: bottom - top }
+    }
+}
+
+/// Font wide extents along one orientation. `descender` follows the HarfBuzz
+/// convention and is negative below the baseline for horizontal extents.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FontExtents {
+    ascender: i32,
+    descender: i32,
+    line_gap: i32,
+}
+
+impl FontExtents {
+    fn new(data: &harfbuzz_sys::hb_font_extents_t) -> FontExtents {
+        FontExtents {
+            ascender: data.ascender,
+            descender: data.descender,
+            line_gap: data.line_gap,
+        }
+    }
+
+    pub fn ascender(&self) -> i32 {
+        self.ascender
+    }
+
+    pub fn descender(&self) -> i32 {
+        self.descender
+    }
+
+    pub fn line_gap(&self) -> i32 {
+        self.line_gap
+    }
+
+    /// Distance between the baselines of two consecutive lines.
+    pub fn line_height(&self) -> i32 {
+        self.ascender - self.descender + self.line_gap
+    }
+}
+
+/// Font wide metrics read with `hb_ot_metrics_get_position` from the OS/2,
+/// post and hhea tables. Needs HarfBuzz 2.6 and the `harfbuzz-2-6` feature.
+#[cfg(feature = "harfbuzz-2-6")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontMetric {
+    XHeight,
+    CapHeight,
+    UnderlinePosition,
+    UnderlineThickness,
+    StrikeoutPosition,
+    StrikeoutThickness,
+    SuperscriptEmXOffset,
+    SuperscriptEmYOffset,
+    SuperscriptEmXSize,
+    SuperscriptEmYSize,
+    SubscriptEmXOffset,
+    SubscriptEmYOffset,
+    SubscriptEmXSize,
+    SubscriptEmYSize,
+}
+
+#[cfg(feature = "harfbuzz-2-6")]
+impl FontMetric {
+    fn to_hb_tag(&self) -> u32 {
+        tag(match *self {
+            FontMetric::XHeight => "xhgt",
+            FontMetric::CapHeight => "cpht",
+            FontMetric::UnderlinePosition => "undo",
+            FontMetric::UnderlineThickness => "unds",
+            FontMetric::StrikeoutPosition => "stro",
+            FontMetric::StrikeoutThickness => "strs",
+            FontMetric::SuperscriptEmXOffset => "spxo",
+            FontMetric::SuperscriptEmYOffset => "spyo",
+            FontMetric::SuperscriptEmXSize => "spxs",
+            FontMetric::SuperscriptEmYSize => "spys",
+            FontMetric::SubscriptEmXOffset => "sbxo",
+            FontMetric::SubscriptEmYOffset => "sbyo",
+            FontMetric::SubscriptEmXSize => "sbxs",
+            FontMetric::SubscriptEmYSize => "sbys",
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct HBGlyphPositions {
     positions: Vec<HBGlyphPosition>,
     width: i32,
     height: i32,
+    ink_extents: HBGlyphExtents,
 }
 
 impl HBGlyphPositions {
-    fn new(positions: Vec<HBGlyphPosition>) -> HBGlyphPositions {
+    fn new(positions: Vec<HBGlyphPosition>, ink_extents: HBGlyphExtents) -> HBGlyphPositions {
         let mut width = 0;
         let mut height = 0;
         for pos in &positions {
             width += pos.x_advance();
             height += pos.y_advance();
         }
-        width += positions[positions.len()-1].x_offset();
-        height += positions[positions.len()-1].y_offset();
-        HBGlyphPositions { positions, width, height }
+        if let Some(last) = positions.last() {
+            width += last.x_offset();
+            height += last.y_offset();
+        }
+        HBGlyphPositions { positions, width, height, ink_extents }
     }
 
     pub fn width(&self) -> i32 {
@@ -259,50 +471,318 @@ impl HBGlyphPositions {
     pub fn positions(&self) -> &Vec<HBGlyphPosition> {
         &self.positions
     }
+
+    /// Union of the ink boxes of all glyphs, relative to the origin of the run.
+    pub fn ink_extents(&self) -> HBGlyphExtents {
+        self.ink_extents
+    }
+}
+
+// Mirrors HB_GLYPH_FLAG_UNSAFE_TO_BREAK, which is stored in hb_glyph_info_t.mask
+const HB_GLYPH_FLAG_UNSAFE_TO_BREAK: u32 = 0x00000001;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HBShapedGlyph {
+    glyph_index: u32,
+    cluster: u32,
+    x_advance: i32,
+    y_advance: i32,
+    x_offset: i32,
+    y_offset: i32,
+    unsafe_to_break: bool,
+}
+
+impl HBShapedGlyph {
+    fn new(info: &harfbuzz_sys::hb_glyph_info_t, pos: &harfbuzz_sys::hb_glyph_position_t) -> HBShapedGlyph {
+        HBShapedGlyph {
+            glyph_index: info.codepoint,
+            cluster: info.cluster,
+            x_advance: pos.x_advance,
+            y_advance: pos.y_advance,
+            x_offset: pos.x_offset,
+            y_offset: pos.y_offset,
+            unsafe_to_break: info.mask & HB_GLYPH_FLAG_UNSAFE_TO_BREAK != 0,
+        }
+    }
+
+    pub fn glyph_index(&self) -> u32 {
+        self.glyph_index
+    }
+
+    /// Offset of the first character that produced this glyph, in the code
+    /// units of the input (see `ShapeText`).
+    pub fn cluster(&self) -> u32 {
+        self.cluster
+    }
+
+    pub fn x_advance(&self) -> i32 {
+        self.x_advance
+    }
+
+    pub fn y_advance(&self) -> i32 {
+        self.y_advance
+    }
+
+    pub fn x_offset(&self) -> i32 {
+        self.x_offset
+    }
+
+    pub fn y_offset(&self) -> i32 {
+        self.y_offset
+    }
+
+    /// True when breaking the text before this glyph requires shaping again.
+    pub fn is_unsafe_to_break(&self) -> bool {
+        self.unsafe_to_break
+    }
 }
 
 impl HBFace {
 
     #[cfg(any(target_os="ios", target_os="macos"))]
-    pub fn from_cg_font(cg_font: CGFontRef) -> HBFace {
+    pub fn from_cg_font(cg_font: CGFontRef) -> Result<HBFace, Error> {
         if cg_font.is_null() {
-            panic!("Attempt to create font with null CGFontRef");
+            return Err(Error::InvalidFontData);
         }
 
         let face = unsafe { hb_coretext_face_create(cg_font) };
         let font = unsafe { harfbuzz_sys::hb_font_create(face) };
-        HBFace {
+        Ok(HBFace {
             face,
-            font
-        }
+            font,
+            ft_face: None,
+            ppem: None,
+            script_level: 0,
+            constants: read_math_constants(font),
+            lock: Arc::new(Mutex::new(())),
+            owner: None,
+        })
     }
 
-    pub fn from_freetype_font(ft_ref: FTFontRef) -> HBFace {
+    pub fn from_freetype_font(ft_ref: FTFontRef) -> Result<HBFace, Error> {
         if ft_ref.is_null() {
-            panic!("Attempt to create font with null FTFontRef");
+            return Err(Error::InvalidFontData);
         }
 
         let face = unsafe { hb_ft_face_create_referenced(ft_ref) };
         let font = unsafe { hb_ft_font_create_referenced(ft_ref) };
-        HBFace {
+        Ok(HBFace {
             face,
-            font
+            font,
+            ft_face: Some(ft_ref),
+            ppem: None,
+            script_level: 0,
+            constants: read_math_constants(font),
+            lock: Arc::new(Mutex::new(())),
+            owner: None,
+        })
+    }
+
+    /// Returns a face sharing the same font data whose metrics are always in
+    /// font units, whatever size is set on `self`.
+    pub fn font_units(&self) -> HBFace {
+        let _lock = self.lock();
+        let font = unsafe { harfbuzz_sys::hb_font_create(self.face) };
+        unsafe { hb_ot_font_set_funcs(font) };
+        HBFace {
+            face: unsafe { harfbuzz_sys::hb_face_reference(self.face) },
+            font,
+            ft_face: None,
+            ppem: None,
+            script_level: 0,
+            constants: read_math_constants(font),
+            // The face still reads its tables through FreeType
+            lock: self.lock.clone(),
+            owner: self.owner.clone(),
         }
     }
 
+    /// Returns a face for laying out math in `style`. Script and script script
+    /// styles are scaled down by `script_percent_scale_down` and
+    /// `script_script_percent_scale_down` and shape with the `ssty` feature
+    /// set to the script level, which selects the alternates designed for
+    /// small sizes. Text is shaped with the `Zmth` script unless the options
+    /// set another one. The face is a sub-font of `self` and keeps the size
+    /// `self` had when it was created.
+    pub fn font_for_style(&self, style: MathStyle) -> HBFace {
+        let percent = match style.script_level() {
+            0 => 100,
+            1 => self.script_percent_scale_down(),
+            _ => self.script_script_percent_scale_down(),
+        };
+        // Fallbacks from MathML Core for fonts without a MATH table
+        let percent = match (percent, style.script_level()) {
+            (0, 1) => 71,
+            (0, _) => 50,
+            (percent, _) => percent,
+        };
+
+        let _lock = self.lock();
+        let font = unsafe { harfbuzz_sys::hb_font_create_sub_font(self.font) };
+        let (mut x_scale, mut y_scale) = (0, 0);
+        unsafe {
+            harfbuzz_sys::hb_font_get_scale(self.font, &mut x_scale, &mut y_scale);
+            harfbuzz_sys::hb_font_set_scale(font, x_scale * percent / 100, y_scale * percent / 100);
+        }
+
+        let mut derived = HBFace {
+            face: unsafe { harfbuzz_sys::hb_face_reference(self.face) },
+            font,
+            ft_face: None,
+            ppem: None,
+            script_level: style.script_level(),
+            constants: read_math_constants(font),
+            // The parent font reads its glyphs through FreeType
+            lock: self.lock.clone(),
+            owner: self.owner.clone(),
+        };
+        if let Some(ppem) = self.ppem {
+            derived.set_hb_ppem(ppem * percent as f32 / 100.);
+        }
+        derived
+    }
+
+    /// Script level the face was created for with `font_for_style`.
+    pub fn script_level(&self) -> u8 {
+        self.script_level
+    }
+
+    /// Sets the size of the font in pixels per em. All metrics are then
+    /// returned in 1/64 pixel units.
+    pub fn set_ppem(&mut self, ppem: f32) -> Result<(), Error> {
+        let scale = (ppem * 64.).round() as i32;
+        let lock = self.lock.clone();
+        let _lock = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match self.ft_face {
+            Some(ft_face) => {
+                // hb-ft reads metrics from the FT_Face, so the size has to be set there
+                let error = unsafe {
+                    FT_Set_Char_Size(ft_face as FT_Face, 0, scale as FT_F26Dot6, 72, 72)
+                };
+                if error != FT_Err_Ok as i32 {
+                    return Err(Error::from_ft_error(error));
+                }
+                self.ft_font_changed(ft_face, ppem);
+            },
+            None => unsafe { harfbuzz_sys::hb_font_set_scale(self.font, scale, scale) }
+        }
+        self.set_hb_ppem(ppem);
+        Ok(())
+    }
+
+    /// Sets the size of the font in points for a device with `dpi` dots per inch.
+    pub fn set_size_points(&mut self, points: f32, dpi: f32) -> Result<(), Error> {
+        self.set_ppem(points * dpi / 72.)
+    }
+
+    /// Called after the size of the underlying FT_Face was changed directly.
+    pub(crate) fn freetype_size_changed(&mut self, ppem: f32) {
+        // Locked through a clone of the Arc, since the face is updated under it
+        let lock = self.lock.clone();
+        let _lock = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(ft_face) = self.ft_face {
+            self.ft_font_changed(ft_face, ppem);
+        }
+        self.set_hb_ppem(ppem);
+    }
+
+    // Called with the face lock held
+    fn ft_font_changed(&self, ft_face: FTFontRef, ppem: f32) {
+        unsafe { hb_ft_font_changed(self.font) };
+        // hb-ft scales by the units per em, which bitmap only faces do not have
+        if unsafe { (*(ft_face as FT_Face)).units_per_EM } == 0 {
+            let scale = (ppem * 64.).round() as i32;
+            unsafe { harfbuzz_sys::hb_font_set_scale(self.font, scale, scale) };
+        }
+    }
+
+    // Called with the face lock held
+    fn set_hb_ppem(&mut self, ppem: f32) {
+        let rounded = ppem.round() as u32;
+        unsafe { harfbuzz_sys::hb_font_set_ppem(self.font, rounded, rounded) };
+        self.ppem = Some(ppem);
+        self.constants = read_math_constants(self.font);
+    }
+
+    /// Size set with `set_ppem` or `set_size_points`.
+    pub fn ppem(&self) -> Option<f32> {
+        self.ppem
+    }
+
+    /// Number of units per em of the metrics returned by this face.
+    pub fn scale(&self) -> i32 {
+        let (mut x_scale, mut y_scale) = (0, 0);
+        let _lock = self.lock();
+        unsafe { harfbuzz_sys::hb_font_get_scale(self.font, &mut x_scale, &mut y_scale) };
+        y_scale
+    }
+
+    /// Converts a metric returned by this face to pixels. Returns `None` when no
+    /// size has been set.
+    pub fn to_pixels(&self, value: i32) -> Option<f32> {
+        let scale = self.scale();
+        if scale == 0 {
+            return None;
+        }
+        self.ppem.map(|ppem| value as f32 * ppem / scale as f32)
+    }
+
     pub fn index(&self) -> u32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_face_get_index(self.face) }
     }
 
     pub fn upem(&self) -> u32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_face_get_upem(self.face) }
     }
 
     pub fn glyph_count(&self) -> u32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_face_get_glyph_count(self.face) }
     }
 
+    /// English string of the name table entry `name_id`, for example 1 for
+    /// the family name or 6 for the PostScript name. Needs HarfBuzz 2.1 and the
+    /// `harfbuzz-2-6` feature.
+    #[cfg(feature = "harfbuzz-2-6")]
+    pub fn name(&self, name_id: u32) -> Option<String> {
+        let _lock = self.lock();
+        let len = unsafe {
+            hb_ot_name_get_utf8(self.face, name_id, ptr::null(), ptr::null_mut(), ptr::null_mut())
+        };
+        if len == 0 {
+            return None;
+        }
+
+        let mut text: Vec<u8> = vec![0; len as usize + 1];
+        let mut text_size = text.len() as u32;
+        unsafe {
+            hb_ot_name_get_utf8(self.face, name_id, ptr::null(), &mut text_size, text.as_mut_ptr() as *mut _)
+        };
+        text.truncate(text_size as usize);
+        String::from_utf8(text).ok()
+    }
+
+    /// Raw data of the OpenType table `tag`, empty if the font does not have it.
+    pub(crate) fn table_data(&self, tag: u32) -> Vec<u8> {
+        let _lock = self.lock();
+        unsafe {
+            let blob = harfbuzz_sys::hb_face_reference_table(self.face, tag);
+            let mut len = 0;
+            let data = harfbuzz_sys::hb_blob_get_data(blob, &mut len);
+            let result = if data.is_null() {
+                Vec::new()
+            } else {
+                slice::from_raw_parts(data as *const u8, len as usize).to_vec()
+            };
+            harfbuzz_sys::hb_blob_destroy(blob);
+            result
+        }
+    }
+
     pub fn glyph_index(&self, unicode: u32) -> Option<u32> {
+        let _lock = self.lock();
         unsafe {
             let mut glyph: harfbuzz_sys::hb_codepoint_t = 0;
             let have_glyph = harfbuzz_sys::hb_font_get_nominal_glyph(self.font, unicode, &mut glyph);
@@ -313,87 +793,205 @@ impl HBFace {
         }
     }
 
+    /// Glyph of `ch` in the given `mathvariant`, for example U+1D499 for `x` in
+    /// `BoldItalic`. Returns `None` if the font does not cover the character.
+    pub fn glyph_index_with_variant(&self, ch: char, variant: MathVariant) -> Option<u32> {
+        self.glyph_index(variant.apply(ch) as u32)
+    }
+
+    /// Ink bounding box of the glyph, or `None` if the font has no such glyph.
+    pub fn glyph_extents(&self, glyph_index: u32) -> Option<HBGlyphExtents> {
+        let mut extents = harfbuzz_sys::hb_glyph_extents_t {
+            x_bearing: 0,
+            y_bearing: 0,
+            width: 0,
+            height: 0,
+        };
+        let have_extents = {
+            let _lock = self.lock();
+            unsafe { harfbuzz_sys::hb_font_get_glyph_extents(self.font, glyph_index, &mut extents) }
+        };
+        if have_extents != 0 {
+            return Some(HBGlyphExtents::new(&extents));
+        }
+        None
+    }
+
     pub fn ascent(&self) -> i32 {
-        self.extends().ascender
+        self.h_extents().ascender
     }
 
     pub fn descent(&self) -> i32 {
-        self.extends().descender
-    }
-
-    fn extends(&self) -> harfbuzz_sys::hb_font_extents_t {
-        let mut extends = harfbuzz_sys::hb_font_extents_t {
-            ascender: 0,
-            descender: 0,
-            line_gap: 0,
-            reserved1: 0,
-            reserved2: 0,
-            reserved3: 0,
-            reserved4: 0,
-            reserved5: 0,
-            reserved6: 0,
-            reserved7: 0,
-            reserved8: 0,
-            reserved9: 0,
-        };
-        unsafe {
-            harfbuzz_sys::hb_font_get_h_extents(
-                self.font,
-                &mut extends
-            );
+        self.h_extents().descender
+    }
+
+    pub fn line_gap(&self) -> i32 {
+        self.h_extents().line_gap
+    }
+
+    /// Extents used for horizontal text.
+    pub fn h_extents(&self) -> FontExtents {
+        let mut extents = empty_font_extents();
+        let _lock = self.lock();
+        unsafe { harfbuzz_sys::hb_font_get_h_extents(self.font, &mut extents) };
+        FontExtents::new(&extents)
+    }
+
+    /// Extents used for vertical text. Fonts without vertical metrics report
+    /// zero.
+    pub fn v_extents(&self) -> FontExtents {
+        let mut extents = empty_font_extents();
+        let _lock = self.lock();
+        unsafe { harfbuzz_sys::hb_font_get_v_extents(self.font, &mut extents) };
+        FontExtents::new(&extents)
+    }
+
+    pub fn extents(&self, direction: HBDirection) -> FontExtents {
+        if direction.is_vertical() {
+            return self.v_extents();
+        }
+        self.h_extents()
+    }
+
+    /// Value of a font wide metric, or `None` if the font does not define it.
+    #[cfg(feature = "harfbuzz-2-6")]
+    pub fn metric(&self, metric: FontMetric) -> Option<i32> {
+        let mut position = 0;
+        let found = {
+            let _lock = self.lock();
+            unsafe { hb_ot_metrics_get_position(self.font, metric.to_hb_tag(), &mut position) }
         };
+        if found != 0 {
+            return Some(position);
+        }
+        None
+    }
+
+    #[cfg(feature = "harfbuzz-2-6")]
+    pub fn x_height(&self) -> Option<i32> {
+        self.metric(FontMetric::XHeight)
+    }
+
+    #[cfg(feature = "harfbuzz-2-6")]
+    pub fn cap_height(&self) -> Option<i32> {
+        self.metric(FontMetric::CapHeight)
+    }
+
+    pub fn measure<T: ShapeText + ?Sized>(&self, text: &T, options: &ShapeOptions) -> Result<HBGlyphPositions, Error> {
+        self.shape_buffer(text, options, |glyph_infos, glyph_positions| {
+            let mut positions = Vec::with_capacity(glyph_positions.len());
+            let mut ink_extents = HBGlyphExtents::default();
+            let (mut x, mut y) = (0, 0);
+            for (info, pos) in glyph_infos.iter().zip(glyph_positions) {
+                if let Some(extents) = self.glyph_extents(info.codepoint) {
+                    ink_extents = ink_extents.union(&extents, x + pos.x_offset, y + pos.y_offset);
+                }
+                x += pos.x_advance;
+                y += pos.y_advance;
+                positions.push(HBGlyphPosition::new(info.codepoint, pos));
+            }
+            HBGlyphPositions::new(positions, ink_extents)
+        })
+    }
 
-        return extends;
+    /// Shapes `text` and returns one record per output glyph, in visual order.
+    pub fn shape<T: ShapeText + ?Sized>(&self, text: &T, options: &ShapeOptions) -> Result<Vec<HBShapedGlyph>, Error> {
+        self.shape_buffer(text, options, |glyph_infos, glyph_positions| {
+            let mut glyphs = Vec::with_capacity(glyph_infos.len());
+            for (info, pos) in glyph_infos.iter().zip(glyph_positions) {
+                glyphs.push(HBShapedGlyph::new(info, pos));
+            }
+            glyphs
+        })
     }
 
-    pub fn measure(&self, text: String, direction: &HBDirection) -> HBGlyphPositions {
-        let char_len = text.chars().count() as  i32;
-        let c_str = CString::new(text).unwrap();
+    fn shape_buffer<T, F, R>(&self, text: &T, options: &ShapeOptions, read: F) -> Result<R, Error>
+        where T: ShapeText + ?Sized,
+              F: FnOnce(&[harfbuzz_sys::hb_glyph_info_t], &[harfbuzz_sys::hb_glyph_position_t]) -> R {
+        let mut features: Vec<harfbuzz_sys::hb_feature_t> = Vec::with_capacity(options.get_features().len() + 1);
+        // Added first so that features in `options` can override it
+        if self.script_level > 0 {
+            features.push(Feature::new("ssty", self.script_level as u32).to_hb_feature());
+        }
+        features.extend(options.get_features().iter().map(|feature| feature.to_hb_feature()));
         let buffer = unsafe { harfbuzz_sys::hb_buffer_create() };
-        let hb_dir = direction.to_hb_dir();
         unsafe {
-            harfbuzz_sys::hb_buffer_set_direction(buffer, hb_dir);
-            harfbuzz_sys::hb_buffer_add_utf8(
-                buffer,
-                c_str.as_ptr(),
-                char_len,
-                0,
-                char_len
-            );
-            harfbuzz_sys::hb_shape(self.font, buffer, ptr::null_mut(), 0);
+            if let Some(direction) = options.get_direction() {
+                harfbuzz_sys::hb_buffer_set_direction(buffer, direction.to_hb_dir());
+            }
+            // `ssty` is only registered for the math script, which the shaper
+            // never guesses from the text
+            let script = options.get_script().or(if self.script_level > 0 { Some("Zmth") } else { None });
+            if let Some(script) = script {
+                harfbuzz_sys::hb_buffer_set_script(
+                    buffer,
+                    harfbuzz_sys::hb_script_from_string(script.as_ptr() as *const _, script.len() as i32)
+                );
+            }
+            if let Some(language) = options.get_language() {
+                harfbuzz_sys::hb_buffer_set_language(
+                    buffer,
+                    harfbuzz_sys::hb_language_from_string(language.as_ptr() as *const _, language.len() as i32)
+                );
+            }
+        }
+
+        if let Err(err) = text.add_to_buffer(buffer) {
+            unsafe { harfbuzz_sys::hb_buffer_destroy(buffer) };
+            return Err(err);
+        }
+
+        unsafe {
+            harfbuzz_sys::hb_buffer_guess_segment_properties(buffer);
+            let _lock = self.lock();
+            harfbuzz_sys::hb_shape(self.font, buffer, features.as_ptr(), features.len() as u32);
         }
 
         let mut num_glyphs: u32 = 0;
+        let glyph_infos = unsafe { harfbuzz_sys::hb_buffer_get_glyph_infos(buffer, &mut num_glyphs) };
         let glyph_positions = unsafe { harfbuzz_sys::hb_buffer_get_glyph_positions(buffer, &mut num_glyphs) };
 
-        let glyph_positions = unsafe { slice::from_raw_parts(glyph_positions, num_glyphs as usize) };
-
-        let mut positions = Vec::with_capacity(glyph_positions.len());
-        for pos in glyph_positions {
-            positions.push(HBGlyphPosition::new(pos));
-        }
+        // An empty buffer may not have allocated its arrays
+        let result = if num_glyphs == 0 {
+            read(&[], &[])
+        } else {
+            unsafe {
+                read(slice::from_raw_parts(glyph_infos, num_glyphs as usize),
+                     slice::from_raw_parts(glyph_positions, num_glyphs as usize))
+            }
+        };
 
         unsafe {
             harfbuzz_sys::hb_buffer_destroy(buffer);
         }
 
-        HBGlyphPositions::new(positions)
-
+        Ok(result)
     }
 
     pub fn has_ot_math_table(&self) -> bool {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_has_data(self.face) } != 0;
     }
 
+    pub fn require_ot_math_table(&self) -> Result<(), Error> {
+        if self.has_ot_math_table() {
+            return Ok(());
+        }
+        Err(Error::MissingMathTable)
+    }
+
     pub fn italics_correction(&self, glyph_index: u32) -> i32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_get_glyph_italics_correction(self.font, glyph_index) }
     }
 
     pub fn top_accent_attachment(&self, glyph_index: u32) -> i32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_get_glyph_top_accent_attachment(self.font, glyph_index) }
     }
 
     pub fn is_glyph_extended_shape(&self, glyph_index: u32) -> bool {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_is_glyph_extended_shape(self.face, glyph_index) != 0 }
     }
 
@@ -414,6 +1012,7 @@ impl HBFace {
     }
 
     fn glyph_kerning(&self, glyph_index: u32, correction_height: i32, kern: harfbuzz_sys::hb_ot_math_kern_t) -> i32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_get_glyph_kerning(self.font, glyph_index, kern, correction_height) }
     }
 
@@ -422,6 +1021,7 @@ impl HBFace {
     }
 
     pub fn glyph_assembly(&self, glyph_index: u32, direction: HBDirection) -> HBGlyphAssembly {
+        let _lock = self.lock();
         let mut italics_correction: i32 = 0;
         let count = unsafe {
             harfbuzz_sys::hb_ot_math_get_glyph_assembly(
@@ -466,9 +1066,27 @@ impl HBFace {
     }
 
     fn math_constant(&self, constant: harfbuzz_sys::hb_ot_math_constant_t) -> i32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_get_constant(self.font, constant) }
     }
 
+    pub(crate) fn set_owner(&mut self, owner: Arc<dyn FaceOwner>) {
+        self.owner = Some(owner);
+    }
+
+    /// Serialises access to the underlying font. Must not be held while
+    /// calling other methods of the face.
+    pub(crate) fn lock(&self) -> MutexGuard<()> {
+        self.lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Every OpenType MATH constant at the current size. The values are read
+    /// once when the face is created or resized, so prefer this over the
+    /// individual getters when many constants are needed.
+    pub fn math_constants(&self) -> &MathConstants {
+        &self.constants
+    }
+
     pub fn script_percent_scale_down(&self) -> i32 {
         return self.math_constant(harfbuzz_sys::hb_ot_math_constant_t::HB_OT_MATH_CONSTANT_SCRIPT_PERCENT_SCALE_DOWN);
     }
@@ -693,9 +1311,21 @@ impl HBFace {
     }
 
     pub fn min_connector_overlap(&self, direction: harfbuzz_sys::hb_direction_t) -> i32 {
+        let _lock = self.lock();
         return unsafe { harfbuzz_sys::hb_ot_math_get_min_connector_overlap(self.font, direction) };
     }
 
+    /// Size of the glyph along `direction`: the advance width for horizontal
+    /// directions and the ink height for vertical ones.
+    pub(crate) fn glyph_size(&self, glyph_index: u32, direction: HBDirection) -> i32 {
+        if !direction.is_vertical() {
+            let _lock = self.lock();
+            return unsafe { harfbuzz_sys::hb_font_get_glyph_h_advance(self.font, glyph_index) };
+        }
+
+        self.glyph_extents(glyph_index).map_or(0, |extents| -extents.height())
+    }
+
     pub fn min_connector_overlap_vertical(&self) -> i32 {
         return self.min_connector_overlap(harfbuzz_sys::hb_direction_t::HB_DIRECTION_TTB);
     }
@@ -707,6 +1337,9 @@ impl HBFace {
 
 impl Drop for HBFace {
     fn drop(&mut self) {
+        // The owner lock is always taken before the face lock
+        let _owner_lock = self.owner.as_ref().map(|owner| owner.lock());
+        let _lock = self.lock();
         unsafe {
             harfbuzz_sys::hb_face_destroy(self.face);
             harfbuzz_sys::hb_font_destroy(self.font);
@@ -714,10 +1347,44 @@ impl Drop for HBFace {
     }
 }
 
+// Reads all MATH constants of `font`. Callers hold the face lock, or own a
+// face that is not shared yet.
+fn read_math_constants(font: *mut harfbuzz_sys::hb_font_t) -> MathConstants {
+    MathConstants::new(|constant| unsafe { harfbuzz_sys::hb_ot_math_get_constant(font, constant) })
+}
+
+fn empty_font_extents() -> harfbuzz_sys::hb_font_extents_t {
+    harfbuzz_sys::hb_font_extents_t {
+        ascender: 0,
+        descender: 0,
+        line_gap: 0,
+        reserved1: 0,
+        reserved2: 0,
+        reserved3: 0,
+        reserved4: 0,
+        reserved5: 0,
+        reserved6: 0,
+        reserved7: 0,
+        reserved8: 0,
+        reserved9: 0,
+    }
+}
+
 extern {
     #[cfg(any(target_os="ios", target_os="macos"))]    
     fn hb_coretext_face_create(reference: CGFontRef) -> *mut harfbuzz_sys::hb_face_t;
 
     fn hb_ft_face_create_referenced(reference: FTFontRef) -> *mut harfbuzz_sys::hb_face_t;
     fn hb_ft_font_create_referenced(reference: FTFontRef) -> *mut harfbuzz_sys::hb_font_t;
+    fn hb_ft_font_changed(font: *mut harfbuzz_sys::hb_font_t);
+
+    fn hb_ot_font_set_funcs(font: *mut harfbuzz_sys::hb_font_t);
+
+    #[cfg(feature = "harfbuzz-2-6")]
+    fn hb_ot_metrics_get_position(font: *mut harfbuzz_sys::hb_font_t, metrics_tag: u32,
+                                  position: *mut harfbuzz_sys::hb_position_t) -> harfbuzz_sys::hb_bool_t;
+
+    #[cfg(feature = "harfbuzz-2-6")]
+    fn hb_ot_name_get_utf8(face: *mut harfbuzz_sys::hb_face_t, name_id: u32, language: harfbuzz_sys::hb_language_t,
+                           text_size: *mut u32, text: *mut c_char) -> u32;
 }
\ No newline at end of file
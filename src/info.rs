@@ -0,0 +1,209 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+//! Naming and licensing information of a font, read from its name, OS/2 and
+//! head tables.
+
+use ::harfbuzz::HBFace;
+use ::shaping::tag;
+
+const NAME_FAMILY: u32 = 1;
+const NAME_SUBFAMILY: u32 = 2;
+const NAME_FULL_NAME: u32 = 4;
+const NAME_VERSION: u32 = 5;
+const NAME_POSTSCRIPT_NAME: u32 = 6;
+const NAME_TYPOGRAPHIC_FAMILY: u32 = 16;
+const NAME_TYPOGRAPHIC_SUBFAMILY: u32 = 17;
+
+// Seconds between the head table epoch, 1904-01-01, and the Unix epoch
+const HEAD_EPOCH_OFFSET: i64 = 2082844800;
+
+/// How a font may be embedded in documents, from the OS/2 `fsType` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingUsage {
+    Installable,
+    Restricted,
+    PreviewAndPrint,
+    Editable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EmbeddingPermissions {
+    fs_type: u16,
+}
+
+impl EmbeddingPermissions {
+    pub fn new(fs_type: u16) -> EmbeddingPermissions {
+        EmbeddingPermissions { fs_type }
+    }
+
+    pub fn fs_type(&self) -> u16 {
+        self.fs_type
+    }
+
+    /// Least restrictive usage allowed. Old fonts may set several usage bits.
+    pub fn usage(&self) -> EmbeddingUsage {
+        if self.fs_type & 0x0008 != 0 {
+            EmbeddingUsage::Editable
+        } else if self.fs_type & 0x0004 != 0 {
+            EmbeddingUsage::PreviewAndPrint
+        } else if self.fs_type & 0x0002 != 0 {
+            EmbeddingUsage::Restricted
+        } else {
+            EmbeddingUsage::Installable
+        }
+    }
+
+    pub fn allows_subsetting(&self) -> bool {
+        self.fs_type & 0x0100 == 0
+    }
+
+    pub fn is_bitmap_only(&self) -> bool {
+        self.fs_type & 0x0200 != 0
+    }
+}
+
+/// Result of `FontInfo::read`. Names are the English entries of the name table;
+/// family and subfamily prefer the typographic names when present.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontInfo {
+    family: Option<String>,
+    subfamily: Option<String>,
+    full_name: Option<String>,
+    postscript_name: Option<String>,
+    version: Option<String>,
+    font_revision: f32,
+    weight_class: u16,
+    width_class: u16,
+    is_italic: bool,
+    embedding: EmbeddingPermissions,
+    created: Option<i64>,
+    modified: Option<i64>,
+}
+
+impl FontInfo {
+    /// Reads the name, OS/2 and head tables of `face`. Name strings are
+    /// `None` without the `harfbuzz-2-6` feature.
+    pub fn read(face: &HBFace) -> FontInfo {
+        let os2 = face.table_data(tag("OS/2"));
+        let head = face.table_data(tag("head"));
+
+        let fs_selection = read_u16(&os2, 62);
+        let mac_style = read_u16(&head, 44);
+        let is_italic = match fs_selection {
+            Some(fs_selection) => fs_selection & 0x0001 != 0,
+            None => mac_style.map_or(false, |mac_style| mac_style & 0x0002 != 0),
+        };
+
+        FontInfo {
+            family: read_name(face, NAME_TYPOGRAPHIC_FAMILY).or_else(|| read_name(face, NAME_FAMILY)),
+            subfamily: read_name(face, NAME_TYPOGRAPHIC_SUBFAMILY).or_else(|| read_name(face, NAME_SUBFAMILY)),
+            full_name: read_name(face, NAME_FULL_NAME),
+            postscript_name: read_name(face, NAME_POSTSCRIPT_NAME),
+            version: read_name(face, NAME_VERSION),
+            font_revision: read_i32(&head, 4).map_or(0., |revision| revision as f32 / 65536.),
+            weight_class: read_u16(&os2, 4).unwrap_or(400),
+            width_class: read_u16(&os2, 6).unwrap_or(5),
+            is_italic,
+            embedding: EmbeddingPermissions::new(read_u16(&os2, 8).unwrap_or(0)),
+            created: read_i64(&head, 20).map(|date| date - HEAD_EPOCH_OFFSET),
+            modified: read_i64(&head, 28).map(|date| date - HEAD_EPOCH_OFFSET),
+        }
+    }
+
+    pub fn family(&self) -> Option<&str> {
+        self.family.as_ref().map(|name| name.as_str())
+    }
+
+    pub fn subfamily(&self) -> Option<&str> {
+        self.subfamily.as_ref().map(|name| name.as_str())
+    }
+
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_ref().map(|name| name.as_str())
+    }
+
+    pub fn postscript_name(&self) -> Option<&str> {
+        self.postscript_name.as_ref().map(|name| name.as_str())
+    }
+
+    /// Version string of the name table, for example `Version 2.00`.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_ref().map(|name| name.as_str())
+    }
+
+    /// `fontRevision` of the head table.
+    pub fn font_revision(&self) -> f32 {
+        self.font_revision
+    }
+
+    /// OS/2 weight class, 400 for regular and 700 for bold.
+    pub fn weight_class(&self) -> u16 {
+        self.weight_class
+    }
+
+    /// OS/2 width class from 1 (ultra condensed) to 9 (ultra expanded).
+    pub fn width_class(&self) -> u16 {
+        self.width_class
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.is_italic
+    }
+
+    pub fn embedding(&self) -> EmbeddingPermissions {
+        self.embedding
+    }
+
+    /// Creation date in seconds since the Unix epoch.
+    pub fn created(&self) -> Option<i64> {
+        self.created
+    }
+
+    /// Modification date in seconds since the Unix epoch.
+    pub fn modified(&self) -> Option<i64> {
+        self.modified
+    }
+}
+
+#[cfg(feature = "harfbuzz-2-6")]
+fn read_name(face: &HBFace, name_id: u32) -> Option<String> {
+    face.name(name_id)
+}
+
+#[cfg(not(feature = "harfbuzz-2-6"))]
+fn read_name(_face: &HBFace, _name_id: u32) -> Option<String> {
+    None
+}
+
+// Table fields are big endian
+fn read_be(data: &[u8], offset: usize, len: usize) -> Option<u64> {
+    data.get(offset..offset + len)
+        .map(|bytes| bytes.iter().fold(0, |value, &byte| (value << 8) | byte as u64))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    read_be(data, offset, 2).map(|value| value as u16)
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    read_be(data, offset, 4).map(|value| value as u32 as i32)
+}
+
+fn read_i64(data: &[u8], offset: usize) -> Option<i64> {
+    read_be(data, offset, 8).map(|value| value as i64)
+}
@@ -18,8 +18,15 @@
 extern crate harfbuzz_sys;
 extern crate freetype_sys;
 
+pub mod error;
 pub mod harfbuzz;
 pub mod freetype;
+pub mod info;
+pub mod math;
+pub mod outline;
+pub mod raster;
+pub mod render;
+pub mod shaping;
 
 #[cfg(test)]
 mod tests {
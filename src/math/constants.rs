@@ -0,0 +1,117 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use ::harfbuzz_sys::hb_ot_math_constant_t;
+
+macro_rules! math_constants {
+    (
+        percent { $($percent:ident => $percent_constant:ident,)* }
+        units { $($unit:ident => $unit_constant:ident,)* }
+    ) => {
+        /// Snapshot of all values in the OpenType MATH constants table.
+        ///
+        /// Percentages are stored as is. All other values are in the units of the
+        /// `HBFace` they were read from, or scaled by `MathConstants::scaled`.
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct MathConstants<T = i32> {
+            $(pub $percent: i32,)*
+            $(pub $unit: T,)*
+        }
+
+        impl MathConstants<i32> {
+            pub(crate) fn new<F>(constant: F) -> MathConstants<i32>
+                where F: Fn(hb_ot_math_constant_t) -> i32 {
+                MathConstants {
+                    $($percent: constant(hb_ot_math_constant_t::$percent_constant),)*
+                    $($unit: constant(hb_ot_math_constant_t::$unit_constant),)*
+                }
+            }
+
+            /// Returns the constants multiplied by `scale`, usually `ppem / upem`.
+            /// Percentages are left untouched.
+            pub fn scaled(&self, scale: f32) -> MathConstants<f32> {
+                MathConstants {
+                    $($percent: self.$percent,)*
+                    $($unit: self.$unit as f32 * scale,)*
+                }
+            }
+        }
+    }
+}
+
+math_constants! {
+    percent {
+        script_percent_scale_down => HB_OT_MATH_CONSTANT_SCRIPT_PERCENT_SCALE_DOWN,
+        script_script_percent_scale_down => HB_OT_MATH_CONSTANT_SCRIPT_SCRIPT_PERCENT_SCALE_DOWN,
+        radical_degree_bottom_raise_percent => HB_OT_MATH_CONSTANT_RADICAL_DEGREE_BOTTOM_RAISE_PERCENT,
+    }
+    units {
+        delimited_sub_formula_min_height => HB_OT_MATH_CONSTANT_DELIMITED_SUB_FORMULA_MIN_HEIGHT,
+        display_operator_min_height => HB_OT_MATH_CONSTANT_DISPLAY_OPERATOR_MIN_HEIGHT,
+        math_leading => HB_OT_MATH_CONSTANT_MATH_LEADING,
+        axis_height => HB_OT_MATH_CONSTANT_AXIS_HEIGHT,
+        accent_base_height => HB_OT_MATH_CONSTANT_ACCENT_BASE_HEIGHT,
+        flattened_accent_base_height => HB_OT_MATH_CONSTANT_FLATTENED_ACCENT_BASE_HEIGHT,
+        subscript_shift_down => HB_OT_MATH_CONSTANT_SUBSCRIPT_SHIFT_DOWN,
+        subscript_top_max => HB_OT_MATH_CONSTANT_SUBSCRIPT_TOP_MAX,
+        subscript_baseline_drop_min => HB_OT_MATH_CONSTANT_SUBSCRIPT_BASELINE_DROP_MIN,
+        superscript_shift_up => HB_OT_MATH_CONSTANT_SUPERSCRIPT_SHIFT_UP,
+        superscript_shift_up_cramped => HB_OT_MATH_CONSTANT_SUPERSCRIPT_SHIFT_UP_CRAMPED,
+        superscript_bottom_min => HB_OT_MATH_CONSTANT_SUPERSCRIPT_BOTTOM_MIN,
+        superscript_baseline_drop_max => HB_OT_MATH_CONSTANT_SUPERSCRIPT_BASELINE_DROP_MAX,
+        sub_superscript_gap_min => HB_OT_MATH_CONSTANT_SUB_SUPERSCRIPT_GAP_MIN,
+        superscript_bottom_max_with_subscript => HB_OT_MATH_CONSTANT_SUPERSCRIPT_BOTTOM_MAX_WITH_SUBSCRIPT,
+        space_after_script => HB_OT_MATH_CONSTANT_SPACE_AFTER_SCRIPT,
+        upper_limit_gap_min => HB_OT_MATH_CONSTANT_UPPER_LIMIT_GAP_MIN,
+        upper_limit_baseline_rise_min => HB_OT_MATH_CONSTANT_UPPER_LIMIT_BASELINE_RISE_MIN,
+        lower_limit_gap_min => HB_OT_MATH_CONSTANT_LOWER_LIMIT_GAP_MIN,
+        lower_limit_baseline_drop_min => HB_OT_MATH_CONSTANT_LOWER_LIMIT_BASELINE_DROP_MIN,
+        stack_top_shift_up => HB_OT_MATH_CONSTANT_STACK_TOP_SHIFT_UP,
+        stack_top_display_style_shift_up => HB_OT_MATH_CONSTANT_STACK_TOP_DISPLAY_STYLE_SHIFT_UP,
+        stack_bottom_shift_down => HB_OT_MATH_CONSTANT_STACK_BOTTOM_SHIFT_DOWN,
+        stack_bottom_display_style_shift_down => HB_OT_MATH_CONSTANT_STACK_BOTTOM_DISPLAY_STYLE_SHIFT_DOWN,
+        stack_gap_min => HB_OT_MATH_CONSTANT_STACK_GAP_MIN,
+        stack_display_style_gap_min => HB_OT_MATH_CONSTANT_STACK_DISPLAY_STYLE_GAP_MIN,
+        stretch_stack_top_shift_up => HB_OT_MATH_CONSTANT_STRETCH_STACK_TOP_SHIFT_UP,
+        stretch_stack_bottom_shift_down => HB_OT_MATH_CONSTANT_STRETCH_STACK_BOTTOM_SHIFT_DOWN,
+        stretch_stack_gap_above_min => HB_OT_MATH_CONSTANT_STRETCH_STACK_GAP_ABOVE_MIN,
+        stretch_stack_gap_below_min => HB_OT_MATH_CONSTANT_STRETCH_STACK_GAP_BELOW_MIN,
+        fraction_numerator_shift_up => HB_OT_MATH_CONSTANT_FRACTION_NUMERATOR_SHIFT_UP,
+        fraction_numerator_display_style_shift_up => HB_OT_MATH_CONSTANT_FRACTION_NUMERATOR_DISPLAY_STYLE_SHIFT_UP,
+        fraction_denominator_shift_down => HB_OT_MATH_CONSTANT_FRACTION_DENOMINATOR_SHIFT_DOWN,
+        fraction_denominator_display_style_shift_down => HB_OT_MATH_CONSTANT_FRACTION_DENOMINATOR_DISPLAY_STYLE_SHIFT_DOWN,
+        fraction_numerator_gap_min => HB_OT_MATH_CONSTANT_FRACTION_NUMERATOR_GAP_MIN,
+        fraction_num_display_style_gap_min => HB_OT_MATH_CONSTANT_FRACTION_NUM_DISPLAY_STYLE_GAP_MIN,
+        fraction_rule_thickness => HB_OT_MATH_CONSTANT_FRACTION_RULE_THICKNESS,
+        fraction_denominator_gap_min => HB_OT_MATH_CONSTANT_FRACTION_DENOMINATOR_GAP_MIN,
+        fraction_denominator_display_style_gap_min => HB_OT_MATH_CONSTANT_FRACTION_DENOM_DISPLAY_STYLE_GAP_MIN,
+        skewed_fraction_horizontal_gap => HB_OT_MATH_CONSTANT_SKEWED_FRACTION_HORIZONTAL_GAP,
+        skewed_fraction_vertical_gap => HB_OT_MATH_CONSTANT_SKEWED_FRACTION_VERTICAL_GAP,
+        overbar_vertical_gap => HB_OT_MATH_CONSTANT_OVERBAR_VERTICAL_GAP,
+        overbar_rule_thickness => HB_OT_MATH_CONSTANT_OVERBAR_RULE_THICKNESS,
+        overbar_extra_ascender => HB_OT_MATH_CONSTANT_OVERBAR_EXTRA_ASCENDER,
+        underbar_vertical_gap => HB_OT_MATH_CONSTANT_UNDERBAR_VERTICAL_GAP,
+        underbar_rule_thickness => HB_OT_MATH_CONSTANT_UNDERBAR_RULE_THICKNESS,
+        underbar_extra_descender => HB_OT_MATH_CONSTANT_UNDERBAR_EXTRA_DESCENDER,
+        radical_vertical_gap => HB_OT_MATH_CONSTANT_RADICAL_VERTICAL_GAP,
+        radical_display_style_vertical_gap => HB_OT_MATH_CONSTANT_RADICAL_DISPLAY_STYLE_VERTICAL_GAP,
+        radical_rule_thickness => HB_OT_MATH_CONSTANT_RADICAL_RULE_THICKNESS,
+        radical_extra_ascender => HB_OT_MATH_CONSTANT_RADICAL_EXTRA_ASCENDER,
+        radical_kern_before_degree => HB_OT_MATH_CONSTANT_RADICAL_KERN_BEFORE_DEGREE,
+        radical_kern_after_degree => HB_OT_MATH_CONSTANT_RADICAL_KERN_AFTER_DEGREE,
+    }
+}
@@ -0,0 +1,129 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::cmp;
+
+use ::harfbuzz::HBFace;
+use ::math::constants::MathConstants;
+use ::math::style::MathStyle;
+use super::{BoxMetrics, Point, Rule};
+
+/// Result of `fraction`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FractionLayout {
+    numerator: Point,
+    denominator: Point,
+    rule: Option<Rule>,
+    metrics: BoxMetrics,
+}
+
+impl FractionLayout {
+    pub fn numerator(&self) -> Point {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> Point {
+        self.denominator
+    }
+
+    /// Fraction bar, `None` for stacks laid out with a zero line thickness.
+    pub fn rule(&self) -> Option<Rule> {
+        self.rule
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+}
+
+/// Lays out `numerator` over `denominator`, both centered horizontally.
+///
+/// The bar is centered on the math axis and is `line_thickness` thick, or
+/// `fraction_rule_thickness` if `None`. A thickness of zero lays out a stack
+/// using the `stack_*` constants instead of the `fraction_*` ones. Display
+/// style uses the display style variants of the constants.
+pub fn fraction(face: &HBFace, numerator: &BoxMetrics, denominator: &BoxMetrics,
+                style: MathStyle, line_thickness: Option<i32>) -> FractionLayout {
+    let constants = face.math_constants();
+    let thickness = line_thickness.unwrap_or(constants.fraction_rule_thickness);
+    let width = cmp::max(numerator.width, denominator.width);
+    let numerator_x = (width - numerator.width) / 2;
+    let denominator_x = (width - denominator.width) / 2;
+
+    if thickness == 0 {
+        let (top_shift, bottom_shift) = stack_shifts(constants, numerator, denominator, style);
+        return FractionLayout {
+            numerator: Point::new(numerator_x, top_shift),
+            denominator: Point::new(denominator_x, -bottom_shift),
+            rule: None,
+            metrics: BoxMetrics::new(width,
+                                     top_shift + numerator.ascent,
+                                     bottom_shift + denominator.descent),
+        };
+    }
+
+    let (shift_up, shift_down, numerator_gap_min, denominator_gap_min) = if style.is_display() {
+        (constants.fraction_numerator_display_style_shift_up,
+         constants.fraction_denominator_display_style_shift_down,
+         constants.fraction_num_display_style_gap_min,
+         constants.fraction_denominator_display_style_gap_min)
+    } else {
+        (constants.fraction_numerator_shift_up,
+         constants.fraction_denominator_shift_down,
+         constants.fraction_numerator_gap_min,
+         constants.fraction_denominator_gap_min)
+    };
+
+    let axis_height = constants.axis_height;
+    let rule_bottom = axis_height - thickness / 2;
+    let rule_top = rule_bottom + thickness;
+
+    let shift_up = cmp::max(shift_up, rule_top + numerator_gap_min + numerator.descent);
+    let shift_down = cmp::max(shift_down, denominator_gap_min + denominator.ascent - rule_bottom);
+
+    FractionLayout {
+        numerator: Point::new(numerator_x, shift_up),
+        denominator: Point::new(denominator_x, -shift_down),
+        rule: Some(Rule::new(0, rule_bottom, width, thickness)),
+        metrics: BoxMetrics::new(width,
+                                 cmp::max(shift_up + numerator.ascent, rule_top),
+                                 cmp::max(shift_down + denominator.descent, -rule_bottom)),
+    }
+}
+
+// Shifts of a stack, increased evenly when the gap between numerator and
+// denominator is below the minimum
+fn stack_shifts(constants: &MathConstants, numerator: &BoxMetrics, denominator: &BoxMetrics,
+                style: MathStyle) -> (i32, i32) {
+    let (top_shift, bottom_shift, gap_min) = if style.is_display() {
+        (constants.stack_top_display_style_shift_up,
+         constants.stack_bottom_display_style_shift_down,
+         constants.stack_display_style_gap_min)
+    } else {
+        (constants.stack_top_shift_up,
+         constants.stack_bottom_shift_down,
+         constants.stack_gap_min)
+    };
+
+    let gap = (top_shift - numerator.descent) + (bottom_shift - denominator.ascent);
+    if gap >= gap_min {
+        return (top_shift, bottom_shift);
+    }
+
+    let delta = gap_min - gap;
+    (top_shift + delta / 2, bottom_shift + delta - delta / 2)
+}
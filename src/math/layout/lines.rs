@@ -0,0 +1,69 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use ::harfbuzz::HBFace;
+use super::{BoxMetrics, Point, Rule};
+
+/// Result of `overline` and `underline`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineLayout {
+    base: Point,
+    rule: Rule,
+    metrics: BoxMetrics,
+}
+
+impl LineLayout {
+    pub fn base(&self) -> Point {
+        self.base
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+}
+
+/// Draws a bar over `base` separated by `overbar_vertical_gap`, with
+/// `overbar_extra_ascender` of space above it.
+pub fn overline(face: &HBFace, base: &BoxMetrics) -> LineLayout {
+    let constants = face.math_constants();
+    let thickness = constants.overbar_rule_thickness;
+    let bottom = base.ascent + constants.overbar_vertical_gap;
+
+    LineLayout {
+        base: Point::new(0, 0),
+        rule: Rule::new(0, bottom, base.width, thickness),
+        metrics: BoxMetrics::new(base.width, bottom + thickness + constants.overbar_extra_ascender, base.descent),
+    }
+}
+
+/// Draws a bar under `base` separated by `underbar_vertical_gap`, with
+/// `underbar_extra_descender` of space below it.
+pub fn underline(face: &HBFace, base: &BoxMetrics) -> LineLayout {
+    let constants = face.math_constants();
+    let thickness = constants.underbar_rule_thickness;
+    let top = base.descent + constants.underbar_vertical_gap;
+
+    LineLayout {
+        base: Point::new(0, 0),
+        rule: Rule::new(0, -(top + thickness), base.width, thickness),
+        metrics: BoxMetrics::new(base.width, base.ascent, top + thickness + constants.underbar_extra_descender),
+    }
+}
@@ -0,0 +1,301 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::cmp;
+
+use ::harfbuzz::{HBFace, HBDirection, HBGlyphPositions};
+use ::error::Error;
+use ::math::style::MathStyle;
+use super::{BoxMetrics, Point, Rule, PositionedGlyph, ScriptPair, UnderOverBox, vertical_glyphs};
+
+/// What a `MathBox` draws.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathBoxContent {
+    /// Glyphs of a shaped run.
+    Glyphs(Vec<PositionedGlyph>),
+    /// Glyphs of a size variant or glyph assembly.
+    Stretched(Vec<PositionedGlyph>),
+    /// A filled rectangle covering the box.
+    Rule,
+    /// Boxes with the position of their origin.
+    Group(Vec<(Point, MathBox)>),
+}
+
+/// Laid out math. Leaves hold glyphs and rules positioned relative to the
+/// origin of their box, and groups hold child boxes, so a renderer only has to
+/// walk one tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MathBox {
+    metrics: BoxMetrics,
+    italics_correction: i32,
+    top_accent_attachment: i32,
+    content: MathBoxContent,
+}
+
+impl MathBox {
+    fn new(metrics: BoxMetrics, content: MathBoxContent) -> MathBox {
+        MathBox { metrics, italics_correction: 0, top_accent_attachment: metrics.width / 2, content }
+    }
+
+    /// Box of a run measured with `HBFace::measure`. A single glyph keeps its
+    /// top accent attachment, and the run takes the italic correction of its
+    /// last glyph.
+    pub fn run(face: &HBFace, positions: &HBGlyphPositions) -> MathBox {
+        let scale = face.scale();
+        let mut glyphs = Vec::with_capacity(positions.positions().len());
+        let (mut x, mut y) = (0, 0);
+        for position in positions.positions() {
+            glyphs.push(PositionedGlyph::new(position.glyph_index(), x + position.x_offset(),
+                                             y + position.y_offset(), scale));
+            x += position.x_advance();
+            y += position.y_advance();
+        }
+
+        let metrics = BoxMetrics::from(positions);
+        let italics_correction = glyphs.last().map_or(0, |glyph| face.italics_correction(glyph.glyph_index));
+        let top_accent_attachment = if glyphs.len() == 1 {
+            face.top_accent_attachment(glyphs[0].glyph_index)
+        } else {
+            metrics.width / 2
+        };
+        MathBox { metrics, italics_correction, top_accent_attachment, content: MathBoxContent::Glyphs(glyphs) }
+    }
+
+    /// Box of a single glyph.
+    pub fn glyph(face: &HBFace, glyph_index: u32) -> MathBox {
+        let extents = face.glyph_extents(glyph_index).unwrap_or_default();
+        let metrics = BoxMetrics::new(face.glyph_size(glyph_index, HBDirection::LTR),
+                                      extents.ascent(), extents.descent());
+        MathBox {
+            metrics,
+            italics_correction: face.italics_correction(glyph_index),
+            top_accent_attachment: face.top_accent_attachment(glyph_index),
+            content: MathBoxContent::Glyphs(vec![PositionedGlyph::new(glyph_index, 0, 0, face.scale())]),
+        }
+    }
+
+    /// Box of `glyph_index` stretched to `size` with `HBFace::stretch_glyph`.
+    /// Vertical constructions are centered on the math axis, as for fences and
+    /// other symmetric stretchy operators.
+    pub fn stretched(face: &HBFace, glyph_index: u32, direction: HBDirection, size: i32) -> MathBox {
+        if !direction.is_vertical() {
+            let stretched = super::stretch_horizontal(face, glyph_index, size);
+            return MathBox::new(stretched.metrics(), MathBoxContent::Stretched(stretched.glyphs().clone()));
+        }
+
+        let stretched = face.stretch_glyph(glyph_index, direction, size);
+        let bottom = face.axis_height() - stretched.advance() / 2;
+        let (glyphs, width) = vertical_glyphs(face, &stretched, 0, bottom);
+        let mut math_box = MathBox::new(BoxMetrics::new(width, bottom + stretched.advance(), -bottom),
+                                        MathBoxContent::Stretched(glyphs));
+        math_box.italics_correction = stretched.italics_correction();
+        math_box
+    }
+
+    /// Filled rectangle sitting on the baseline.
+    pub fn rule(width: i32, height: i32) -> MathBox {
+        MathBox::new(BoxMetrics::new(width, height, 0), MathBoxContent::Rule)
+    }
+
+    /// Box holding `children` at the given positions.
+    pub fn group(metrics: BoxMetrics, children: Vec<(Point, MathBox)>) -> MathBox {
+        MathBox::new(metrics, MathBoxContent::Group(children))
+    }
+
+    /// Places the boxes one after the other on a common baseline.
+    pub fn row(children: Vec<MathBox>) -> MathBox {
+        let mut metrics = BoxMetrics::default();
+        let mut positioned = Vec::with_capacity(children.len());
+        for child in children {
+            metrics.ascent = cmp::max(metrics.ascent, child.metrics.ascent);
+            metrics.descent = cmp::max(metrics.descent, child.metrics.descent);
+            let x = metrics.width;
+            metrics.width += child.metrics.width;
+            positioned.push((Point::new(x, 0), child));
+        }
+        let italics_correction = positioned.last().map_or(0, |&(_, ref child)| child.italics_correction);
+        let mut math_box = MathBox::group(metrics, positioned);
+        math_box.italics_correction = italics_correction;
+        math_box
+    }
+
+    pub fn fraction(face: &HBFace, numerator: MathBox, denominator: MathBox, style: MathStyle,
+                    line_thickness: Option<i32>) -> MathBox {
+        let layout = super::fraction(face, &numerator.metrics, &denominator.metrics, style, line_thickness);
+        let mut children = vec![(layout.numerator(), numerator), (layout.denominator(), denominator)];
+        if let Some(rule) = layout.rule() {
+            children.push(rule_child(rule));
+        }
+        MathBox::group(layout.metrics(), children)
+    }
+
+    pub fn radical(face: &HBFace, base: MathBox, degree: Option<MathBox>,
+                   style: MathStyle) -> Result<MathBox, Error> {
+        let layout = super::radical(face, &base.metrics, degree.as_ref().map(|degree| &degree.metrics), style)?;
+        let rule = layout.rule();
+        // The glyphs of the sign are already positioned relative to the origin
+        let sign_metrics = BoxMetrics::new(layout.base().x, rule.y + rule.height, layout.metrics().descent);
+        let sign = MathBox::new(sign_metrics, MathBoxContent::Stretched(layout.glyphs().clone()));
+        let mut children = vec![(Point::new(0, 0), sign), rule_child(rule), (layout.base(), base)];
+        if let (Some(position), Some(degree)) = (layout.degree(), degree) {
+            children.push((position, degree));
+        }
+        Ok(MathBox::group(layout.metrics(), children))
+    }
+
+    /// Attaches scripts after the base. A base made of a single glyph gets its
+    /// math kerning and italic correction applied.
+    pub fn scripts(face: &HBFace, base: MathBox, subscript: Option<MathBox>, superscript: Option<MathBox>,
+                   style: MathStyle) -> MathBox {
+        let base_glyph = base.single_glyph();
+        let layout = super::multiscripts(
+            face, &base.metrics, base_glyph,
+            &[ScriptPair::new(subscript.as_ref().map(|script| &script.metrics),
+                              superscript.as_ref().map(|script| &script.metrics))],
+            &[], style);
+
+        let mut children = vec![(layout.base(), base)];
+        if let (Some(position), Some(script)) = (layout.subscript(), subscript) {
+            children.push((position, script));
+        }
+        if let (Some(position), Some(script)) = (layout.superscript(), superscript) {
+            children.push((position, script));
+        }
+        MathBox::group(layout.metrics(), children)
+    }
+
+    pub fn under_over(face: &HBFace, base: MathBox, under: Option<MathBox>, over: Option<MathBox>,
+                      accent_under: bool, accent: bool) -> MathBox {
+        let layout = super::under_over(face, base.under_over_box(),
+                                       under.as_ref().map(MathBox::under_over_box),
+                                       over.as_ref().map(MathBox::under_over_box),
+                                       accent_under, accent);
+
+        let base_x = layout.base().x;
+        let attachment = base.top_accent_attachment;
+        let mut children = vec![(layout.base(), base)];
+        if let (Some(position), Some(script)) = (layout.under(), under) {
+            children.push((position, script));
+        }
+        if let (Some(position), Some(script)) = (layout.over(), over) {
+            children.push((position, script));
+        }
+        let mut math_box = MathBox::group(layout.metrics(), children);
+        math_box.top_accent_attachment = base_x + attachment;
+        math_box
+    }
+
+    pub fn overline(face: &HBFace, base: MathBox) -> MathBox {
+        let layout = super::overline(face, &base.metrics);
+        MathBox::group(layout.metrics(), vec![(layout.base(), base), rule_child(layout.rule())])
+    }
+
+    pub fn underline(face: &HBFace, base: MathBox) -> MathBox {
+        let layout = super::underline(face, &base.metrics);
+        MathBox::group(layout.metrics(), vec![(layout.base(), base), rule_child(layout.rule())])
+    }
+
+    pub fn with_italics_correction(mut self, italics_correction: i32) -> MathBox {
+        self.italics_correction = italics_correction;
+        self
+    }
+
+    pub fn with_top_accent_attachment(mut self, top_accent_attachment: i32) -> MathBox {
+        self.top_accent_attachment = top_accent_attachment;
+        self
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+
+    pub fn width(&self) -> i32 {
+        self.metrics.width
+    }
+
+    pub fn ascent(&self) -> i32 {
+        self.metrics.ascent
+    }
+
+    pub fn descent(&self) -> i32 {
+        self.metrics.descent
+    }
+
+    pub fn italics_correction(&self) -> i32 {
+        self.italics_correction
+    }
+
+    /// Horizontal position accents placed over the box attach to.
+    pub fn top_accent_attachment(&self) -> i32 {
+        self.top_accent_attachment
+    }
+
+    pub fn content(&self) -> &MathBoxContent {
+        &self.content
+    }
+
+    /// All glyphs of the tree, positioned relative to the origin of this box.
+    pub fn glyphs(&self) -> Vec<PositionedGlyph> {
+        let mut glyphs = Vec::new();
+        self.walk(Point::new(0, 0), &mut |origin, math_box| match math_box.content {
+            MathBoxContent::Glyphs(ref leaf) | MathBoxContent::Stretched(ref leaf) => {
+                glyphs.extend(leaf.iter().map(|glyph| {
+                    PositionedGlyph::new(glyph.glyph_index, origin.x + glyph.x, origin.y + glyph.y, glyph.scale)
+                }));
+            },
+            _ => {}
+        });
+        glyphs
+    }
+
+    /// All rules of the tree, positioned relative to the origin of this box.
+    pub fn rules(&self) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        self.walk(Point::new(0, 0), &mut |origin, math_box| if let MathBoxContent::Rule = math_box.content {
+            let metrics = math_box.metrics;
+            rules.push(Rule::new(origin.x, origin.y - metrics.descent, metrics.width, metrics.height()));
+        });
+        rules
+    }
+
+    fn walk<F: FnMut(Point, &MathBox)>(&self, origin: Point, visit: &mut F) {
+        visit(origin, self);
+        if let MathBoxContent::Group(ref children) = self.content {
+            for &(position, ref child) in children {
+                child.walk(Point::new(origin.x + position.x, origin.y + position.y), visit);
+            }
+        }
+    }
+
+    fn single_glyph(&self) -> Option<u32> {
+        match self.content {
+            MathBoxContent::Glyphs(ref glyphs) if glyphs.len() == 1 => Some(glyphs[0].glyph_index),
+            _ => None
+        }
+    }
+
+    fn under_over_box(&self) -> UnderOverBox {
+        UnderOverBox::new(&self.metrics)
+            .top_accent_attachment(self.top_accent_attachment)
+            .italics_correction(self.italics_correction)
+    }
+}
+
+fn rule_child(rule: Rule) -> (Point, MathBox) {
+    (Point::new(rule.x, rule.y), MathBox::rule(rule.width, rule.height))
+}
+
@@ -0,0 +1,164 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+//! Layout of math constructs following the OpenType MATH specification.
+//!
+//! All functions work in the units of the `HBFace` passed to them, font units
+//! for a face that has no size set. Vertical offsets are measured from the
+//! baseline of the construct and are positive upwards.
+
+mod fraction;
+mod lines;
+mod math_box;
+mod radical;
+mod scripts;
+mod under_over;
+
+pub use self::fraction::{fraction, FractionLayout};
+pub use self::lines::{overline, underline, LineLayout};
+pub use self::math_box::{MathBox, MathBoxContent};
+pub use self::radical::{radical, RadicalLayout};
+pub use self::scripts::{scripts, multiscripts, ScriptPair, ScriptPositions, ScriptsLayout};
+pub use self::under_over::{under_over, accent_glyph, UnderOverBox, UnderOverLayout};
+
+use std::cmp;
+
+use ::harfbuzz::{HBFace, HBDirection, HBGlyphPositions};
+use ::math::stretch::StretchedGlyph;
+
+/// Metrics of a laid out box. `descent` is positive below the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BoxMetrics {
+    pub width: i32,
+    pub ascent: i32,
+    pub descent: i32,
+}
+
+impl BoxMetrics {
+    pub fn new(width: i32, ascent: i32, descent: i32) -> BoxMetrics {
+        BoxMetrics { width, ascent, descent }
+    }
+
+    pub fn height(&self) -> i32 {
+        self.ascent + self.descent
+    }
+}
+
+impl<'a> From<&'a HBGlyphPositions> for BoxMetrics {
+    /// Uses the advance width and the ink ascent and descent of a measured run.
+    fn from(positions: &'a HBGlyphPositions) -> BoxMetrics {
+        let ink = positions.ink_extents();
+        BoxMetrics::new(positions.width(), ink.ascent(), ink.descent())
+    }
+}
+
+/// Position of the origin of a child box, relative to the origin of its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+}
+
+/// Filled rectangle such as a fraction bar. `(x, y)` is its bottom left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rule {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rule {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rule {
+        Rule { x, y, width, height }
+    }
+}
+
+/// Glyph to draw with its origin at `(x, y)`. `scale` is the `HBFace::scale`
+/// of the face the glyph was laid out with, so glyphs of script style faces
+/// can be drawn at their reduced size. Zero stands for the size of the face
+/// used to draw.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PositionedGlyph {
+    pub glyph_index: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale: i32,
+}
+
+impl PositionedGlyph {
+    pub fn new(glyph_index: u32, x: i32, y: i32, scale: i32) -> PositionedGlyph {
+        PositionedGlyph { glyph_index, x, y, scale }
+    }
+}
+
+/// Glyphs of a construction built by `stretch_horizontal`, with the metrics of
+/// their union.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StretchedBox {
+    glyphs: Vec<PositionedGlyph>,
+    metrics: BoxMetrics,
+}
+
+impl StretchedBox {
+    pub fn glyphs(&self) -> &Vec<PositionedGlyph> {
+        &self.glyphs
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+}
+
+/// Stretches `glyph_index` horizontally to cover `width`, as for over and
+/// under braces, arrows and wide accents. The construction starts at x = 0
+/// and its glyphs stay on the baseline.
+pub fn stretch_horizontal(face: &HBFace, glyph_index: u32, width: i32) -> StretchedBox {
+    let stretched = face.stretch_glyph(glyph_index, HBDirection::LTR, width);
+    let scale = face.scale();
+    let mut glyphs = Vec::with_capacity(stretched.parts().len());
+    let mut ascent = 0;
+    let mut descent = 0;
+    for part in stretched.parts() {
+        if let Some(extents) = face.glyph_extents(part.glyph_index()) {
+            ascent = cmp::max(ascent, extents.ascent());
+            descent = cmp::max(descent, extents.descent());
+        }
+        glyphs.push(PositionedGlyph::new(part.glyph_index(), part.offset(), 0, scale));
+    }
+    StretchedBox { glyphs, metrics: BoxMetrics::new(stretched.advance(), ascent, descent) }
+}
+
+// Places the parts of a vertically stretched glyph so that the bottom of the
+// construction is at `bottom`. Returns the glyphs and the width of the widest.
+fn vertical_glyphs(face: &HBFace, stretched: &StretchedGlyph, x: i32, bottom: i32) -> (Vec<PositionedGlyph>, i32) {
+    let scale = face.scale();
+    let mut glyphs = Vec::with_capacity(stretched.parts().len());
+    let mut width = 0;
+    for part in stretched.parts() {
+        let descent = face.glyph_extents(part.glyph_index()).map_or(0, |extents| extents.descent());
+        glyphs.push(PositionedGlyph::new(part.glyph_index(), x, bottom + part.offset() + descent, scale));
+        width = cmp::max(width, face.glyph_size(part.glyph_index(), HBDirection::LTR));
+    }
+    (glyphs, width)
+}
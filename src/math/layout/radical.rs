@@ -0,0 +1,122 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::cmp;
+
+use ::harfbuzz::{HBFace, HBDirection};
+use ::error::Error;
+use ::math::style::MathStyle;
+use super::{BoxMetrics, Point, Rule, PositionedGlyph, vertical_glyphs};
+
+const RADICAL_SIGN: char = '\u{221A}';
+
+/// Result of `radical`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadicalLayout {
+    glyphs: Vec<PositionedGlyph>,
+    rule: Rule,
+    base: Point,
+    degree: Option<Point>,
+    metrics: BoxMetrics,
+}
+
+impl RadicalLayout {
+    /// Glyphs of the radical sign, a single size variant or the parts of an
+    /// assembly.
+    pub fn glyphs(&self) -> &Vec<PositionedGlyph> {
+        &self.glyphs
+    }
+
+    /// Overbar extending the radical sign over the base.
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn base(&self) -> Point {
+        self.base
+    }
+
+    pub fn degree(&self) -> Option<Point> {
+        self.degree
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+}
+
+/// Lays out a square root of `base`, or an nth root if `degree` is given.
+///
+/// The √ glyph is stretched to cover the base, the vertical gap and the
+/// overbar. If the chosen size is taller than needed, the extra space is split
+/// evenly between the gap above the base and the descent. The degree is raised
+/// by `radical_degree_bottom_raise_percent` of the radical height and kerned
+/// with `radical_kern_before_degree` and `radical_kern_after_degree`. Fails
+/// with `Error::MissingGlyph` if the font has no √ glyph.
+pub fn radical(face: &HBFace, base: &BoxMetrics, degree: Option<&BoxMetrics>,
+               style: MathStyle) -> Result<RadicalLayout, Error> {
+    let constants = face.math_constants();
+    let thickness = constants.radical_rule_thickness;
+    let mut gap = if style.is_display() {
+        constants.radical_display_style_vertical_gap
+    } else {
+        constants.radical_vertical_gap
+    };
+
+    let target = base.height() + gap + thickness;
+    let glyph_index = face.glyph_index(RADICAL_SIGN as u32).ok_or(Error::MissingGlyph(RADICAL_SIGN))?;
+    let stretched = face.stretch_glyph(glyph_index, HBDirection::TTB, target);
+    let height = stretched.advance();
+    if height > target {
+        gap += (height - target) / 2;
+    }
+
+    let top = base.ascent + gap + thickness;
+    let bottom = top - height;
+
+    let (radical_x, degree) = match degree {
+        Some(degree) => {
+            let kern_before = constants.radical_kern_before_degree;
+            let kern_after = constants.radical_kern_after_degree;
+            let radical_x = cmp::max(0, kern_before + degree.width + kern_after);
+            let raise = constants.radical_degree_bottom_raise_percent * height / 100;
+            let position = Point::new(radical_x - kern_after - degree.width, bottom + raise);
+            (radical_x, Some((position, degree)))
+        },
+        None => (0, None)
+    };
+
+    let (glyphs, radical_width) = vertical_glyphs(face, &stretched, radical_x, bottom);
+    let base_x = radical_x + radical_width;
+
+    let mut ascent = top + constants.radical_extra_ascender;
+    let mut descent = cmp::max(base.descent, -bottom);
+    let mut width = base_x + base.width;
+    if let Some((position, degree)) = degree {
+        ascent = cmp::max(ascent, position.y + degree.ascent);
+        descent = cmp::max(descent, degree.descent - position.y);
+        width = cmp::max(width, position.x + degree.width);
+    }
+
+    Ok(RadicalLayout {
+        glyphs,
+        rule: Rule::new(base_x, top - thickness, base.width, thickness),
+        base: Point::new(base_x, 0),
+        degree: degree.map(|(position, _)| position),
+        metrics: BoxMetrics::new(width, ascent, descent),
+    })
+}
@@ -0,0 +1,245 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::cmp;
+
+use ::harfbuzz::HBFace;
+use ::math::constants::MathConstants;
+use ::math::style::MathStyle;
+use super::{BoxMetrics, Point};
+
+/// Subscript and superscript attached at the same side of a base. Either may be
+/// missing, as with `<none/>` in `mmultiscripts`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScriptPair<'a> {
+    pub subscript: Option<&'a BoxMetrics>,
+    pub superscript: Option<&'a BoxMetrics>,
+}
+
+impl<'a> ScriptPair<'a> {
+    pub fn new(subscript: Option<&'a BoxMetrics>, superscript: Option<&'a BoxMetrics>) -> ScriptPair<'a> {
+        ScriptPair { subscript, superscript }
+    }
+}
+
+/// Positions of the scripts of a `ScriptPair`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScriptPositions {
+    pub subscript: Option<Point>,
+    pub superscript: Option<Point>,
+}
+
+/// Result of `scripts` and `multiscripts`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptsLayout {
+    base: Point,
+    post_scripts: Vec<ScriptPositions>,
+    pre_scripts: Vec<ScriptPositions>,
+    metrics: BoxMetrics,
+}
+
+impl ScriptsLayout {
+    pub fn base(&self) -> Point {
+        self.base
+    }
+
+    /// Position of the first subscript after the base.
+    pub fn subscript(&self) -> Option<Point> {
+        self.post_scripts.first().and_then(|scripts| scripts.subscript)
+    }
+
+    /// Position of the first superscript after the base.
+    pub fn superscript(&self) -> Option<Point> {
+        self.post_scripts.first().and_then(|scripts| scripts.superscript)
+    }
+
+    /// Positions of the scripts after the base, in the order they were given.
+    pub fn post_scripts(&self) -> &Vec<ScriptPositions> {
+        &self.post_scripts
+    }
+
+    /// Positions of the scripts before the base, in the order they were given.
+    pub fn pre_scripts(&self) -> &Vec<ScriptPositions> {
+        &self.pre_scripts
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+}
+
+/// Attaches `subscript` and `superscript` to the right of `base`, as for
+/// `msub`, `msup` and `msubsup`. See `multiscripts`.
+pub fn scripts(face: &HBFace, base: &BoxMetrics, base_glyph: Option<u32>, subscript: Option<&BoxMetrics>,
+               superscript: Option<&BoxMetrics>, style: MathStyle) -> ScriptsLayout {
+    multiscripts(face, base, base_glyph, &[ScriptPair::new(subscript, superscript)], &[], style)
+}
+
+/// Attaches scripts after and before `base` following the OpenType MATH script
+/// positioning rules, as for `mmultiscripts`.
+///
+/// All subscripts share one baseline and so do all superscripts. The shifts
+/// honour the minimum gap between subscripts and superscripts, and superscripts
+/// are raised less in cramped styles. `base_glyph` is the glyph when the base is
+/// a single character. Its italic correction is added before the superscript and
+/// its math kerning cuts the scripts next to the base into its corners. The
+/// baseline drops only apply to bases that are not a single glyph. Every pair
+/// of scripts is followed, or preceded for pre-scripts, by `space_after_script`.
+pub fn multiscripts(face: &HBFace, base: &BoxMetrics, base_glyph: Option<u32>, post_scripts: &[ScriptPair],
+                    pre_scripts: &[ScriptPair], style: MathStyle) -> ScriptsLayout {
+    let all_scripts = || post_scripts.iter().chain(pre_scripts.iter());
+    let subscripts: Vec<&BoxMetrics> = all_scripts().filter_map(|pair| pair.subscript).collect();
+    let superscripts: Vec<&BoxMetrics> = all_scripts().filter_map(|pair| pair.superscript).collect();
+    let sub_ascent = subscripts.iter().map(|script| script.ascent).max();
+    let sub_descent = subscripts.iter().map(|script| script.descent).max().unwrap_or(0);
+    let sup_ascent = superscripts.iter().map(|script| script.ascent).max().unwrap_or(0);
+    let sup_descent = superscripts.iter().map(|script| script.descent).max();
+
+    let constants = face.math_constants();
+    let (sub_shift, sup_shift) = shifts(constants, base, base_glyph.is_some(), sub_ascent, sup_descent, style);
+
+    let space = constants.space_after_script;
+    let mut x = 0;
+    let mut pre_positions = Vec::with_capacity(pre_scripts.len());
+    for (index, pair) in pre_scripts.iter().enumerate() {
+        x += space;
+        let width = pair_width(pair);
+        let (sub_kern, sup_kern) = match base_glyph {
+            Some(glyph) if index == pre_scripts.len() - 1 =>
+                corner_kerns(face, glyph, base, pair, sub_shift, sup_shift, false),
+            _ => (0, 0)
+        };
+        pre_positions.push(ScriptPositions {
+            subscript: pair.subscript.map(|script| Point::new(x + width - script.width - sub_kern, -sub_shift)),
+            superscript: pair.superscript.map(|script| Point::new(x + width - script.width - sup_kern, sup_shift)),
+        });
+        x += width;
+    }
+
+    let base_x = x;
+    x += base.width;
+    let italics_correction = base_glyph.map_or(0, |glyph| face.italics_correction(glyph));
+
+    let mut post_positions = Vec::with_capacity(post_scripts.len());
+    for (index, pair) in post_scripts.iter().enumerate() {
+        let (mut sub_x, mut sup_x) = (x, x);
+        if index == 0 {
+            sup_x += italics_correction;
+            if let Some(glyph) = base_glyph {
+                let (sub_kern, sup_kern) = corner_kerns(face, glyph, base, pair, sub_shift, sup_shift, true);
+                sub_x += sub_kern;
+                sup_x += sup_kern;
+            }
+        }
+        let sub_end = pair.subscript.map_or(x, |script| sub_x + script.width);
+        let sup_end = pair.superscript.map_or(x, |script| sup_x + script.width);
+        post_positions.push(ScriptPositions {
+            subscript: pair.subscript.map(|_| Point::new(sub_x, -sub_shift)),
+            superscript: pair.superscript.map(|_| Point::new(sup_x, sup_shift)),
+        });
+        x = cmp::max(sub_end, sup_end) + space;
+    }
+
+    let mut ascent = base.ascent;
+    let mut descent = base.descent;
+    if !superscripts.is_empty() {
+        ascent = cmp::max(ascent, sup_shift + sup_ascent);
+        descent = cmp::max(descent, sup_descent.unwrap_or(0) - sup_shift);
+    }
+    if !subscripts.is_empty() {
+        ascent = cmp::max(ascent, sub_ascent.unwrap_or(0) - sub_shift);
+        descent = cmp::max(descent, sub_shift + sub_descent);
+    }
+
+    ScriptsLayout {
+        base: Point::new(base_x, 0),
+        post_scripts: post_positions,
+        pre_scripts: pre_positions,
+        metrics: BoxMetrics::new(x, ascent, descent),
+    }
+}
+
+// Subscript shift down and superscript shift up shared by all scripts
+fn shifts(constants: &MathConstants, base: &BoxMetrics, is_glyph: bool, sub_ascent: Option<i32>,
+          sup_descent: Option<i32>, style: MathStyle) -> (i32, i32) {
+    let mut sub_shift = 0;
+    if let Some(sub_ascent) = sub_ascent {
+        sub_shift = cmp::max(constants.subscript_shift_down, sub_ascent - constants.subscript_top_max);
+        if !is_glyph {
+            sub_shift = cmp::max(sub_shift, base.descent + constants.subscript_baseline_drop_min);
+        }
+    }
+
+    let mut sup_shift = 0;
+    if let Some(sup_descent) = sup_descent {
+        sup_shift = if style.is_cramped() {
+            constants.superscript_shift_up_cramped
+        } else {
+            constants.superscript_shift_up
+        };
+        sup_shift = cmp::max(sup_shift, sup_descent + constants.superscript_bottom_min);
+        if !is_glyph {
+            sup_shift = cmp::max(sup_shift, base.ascent - constants.superscript_baseline_drop_max);
+        }
+    }
+
+    if let (Some(sub_ascent), Some(sup_descent)) = (sub_ascent, sup_descent) {
+        let gap = (sub_shift - sub_ascent) + (sup_shift - sup_descent);
+        let gap_min = constants.sub_superscript_gap_min;
+        if gap < gap_min {
+            // Raise the superscript up to its limit and lower the subscript by
+            // what is left of the missing gap
+            let delta = gap_min - gap;
+            let raise = constants.superscript_bottom_max_with_subscript - (sup_shift - sup_descent);
+            let raise = cmp::max(cmp::min(raise, delta), 0);
+            sup_shift += raise;
+            sub_shift += delta - raise;
+        }
+    }
+
+    (sub_shift, sup_shift)
+}
+
+fn pair_width(pair: &ScriptPair) -> i32 {
+    cmp::max(pair.subscript.map_or(0, |script| script.width),
+             pair.superscript.map_or(0, |script| script.width))
+}
+
+// Math kerning of the subscript and superscript at the right or left corners
+// of `glyph`. The kern is looked up at the top of the subscript and the bottom
+// of the superscript, and at the bottom and top of the base, and the larger of
+// the two is used.
+fn corner_kerns(face: &HBFace, glyph: u32, base: &BoxMetrics, pair: &ScriptPair,
+                sub_shift: i32, sup_shift: i32, right: bool) -> (i32, i32) {
+    let sub_kern = pair.subscript.map_or(0, |script| {
+        let kern = |height| if right {
+            face.glyph_kerning_bottom_right(glyph, height)
+        } else {
+            face.glyph_kerning_bottom_left(glyph, height)
+        };
+        cmp::max(kern(script.ascent - sub_shift), kern(-base.descent))
+    });
+    let sup_kern = pair.superscript.map_or(0, |script| {
+        let kern = |height| if right {
+            face.glyph_kerning_top_right(glyph, height)
+        } else {
+            face.glyph_kerning_top_left(glyph, height)
+        };
+        cmp::max(kern(sup_shift - script.descent), kern(base.ascent))
+    });
+    (sub_kern, sup_kern)
+}
@@ -0,0 +1,166 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::cmp;
+
+use ::harfbuzz::HBFace;
+use ::error::Error;
+use ::shaping::{Feature, ShapeOptions};
+use super::{BoxMetrics, Point};
+
+/// A base or a script of `under_over`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnderOverBox<'a> {
+    pub metrics: &'a BoxMetrics,
+    /// Horizontal position accents attach to, the middle of the box if `None`.
+    pub top_accent_attachment: Option<i32>,
+    /// Italic correction of a base glyph such as an integral. Limits are moved
+    /// apart horizontally by half of it.
+    pub italics_correction: i32,
+}
+
+impl<'a> UnderOverBox<'a> {
+    pub fn new(metrics: &'a BoxMetrics) -> UnderOverBox<'a> {
+        UnderOverBox { metrics, top_accent_attachment: None, italics_correction: 0 }
+    }
+
+    pub fn top_accent_attachment(mut self, attachment: i32) -> UnderOverBox<'a> {
+        self.top_accent_attachment = Some(attachment);
+        self
+    }
+
+    pub fn italics_correction(mut self, italics_correction: i32) -> UnderOverBox<'a> {
+        self.italics_correction = italics_correction;
+        self
+    }
+
+    fn attachment(&self) -> i32 {
+        self.top_accent_attachment.unwrap_or(self.metrics.width / 2)
+    }
+}
+
+/// Result of `under_over`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnderOverLayout {
+    base: Point,
+    under: Option<Point>,
+    over: Option<Point>,
+    metrics: BoxMetrics,
+}
+
+impl UnderOverLayout {
+    pub fn base(&self) -> Point {
+        self.base
+    }
+
+    pub fn under(&self) -> Option<Point> {
+        self.under
+    }
+
+    pub fn over(&self) -> Option<Point> {
+        self.over
+    }
+
+    pub fn metrics(&self) -> BoxMetrics {
+        self.metrics
+    }
+}
+
+/// Lays out `under` below and `over` above `base`, as for `munder`, `mover`
+/// and `munderover`.
+///
+/// Limits are separated from the base by the `lower_limit_*` and
+/// `upper_limit_*` constants and centered. Accents (`accent_under`, `accent`)
+/// are aligned on the top accent attachment points. An over accent is designed
+/// to sit on a base `accent_base_height` tall and is only raised by the part of
+/// the base above that height. An under accent touches the bottom of the base.
+/// Horizontally stretchy accents should be built with `stretch_horizontal` to
+/// the width of the base first.
+pub fn under_over(face: &HBFace, base: UnderOverBox, under: Option<UnderOverBox>, over: Option<UnderOverBox>,
+                  accent_under: bool, accent: bool) -> UnderOverLayout {
+    let constants = face.math_constants();
+    let metrics = base.metrics;
+    let half_correction = base.italics_correction / 2;
+
+    // Horizontal positions relative to the base, fixed up below so that the
+    // leftmost box starts at 0
+    let mut left = 0;
+    let mut right = metrics.width;
+    let mut ascent = metrics.ascent;
+    let mut descent = metrics.descent;
+
+    let over = over.map(|over| {
+        let x = if accent {
+            base.attachment() - over.attachment()
+        } else {
+            (metrics.width - over.metrics.width) / 2 + half_correction
+        };
+        let y = if accent {
+            cmp::max(0, metrics.ascent - constants.accent_base_height)
+        } else {
+            metrics.ascent + cmp::max(constants.upper_limit_gap_min + over.metrics.descent,
+                                      constants.upper_limit_baseline_rise_min)
+        };
+        left = cmp::min(left, x);
+        right = cmp::max(right, x + over.metrics.width);
+        ascent = cmp::max(ascent, y + over.metrics.ascent);
+        Point::new(x, y)
+    });
+
+    let under = under.map(|under| {
+        let x = if accent_under {
+            base.attachment() - under.attachment()
+        } else {
+            (metrics.width - under.metrics.width) / 2 - half_correction
+        };
+        let y = if accent_under {
+            -(metrics.descent + under.metrics.ascent)
+        } else {
+            -(metrics.descent + cmp::max(constants.lower_limit_gap_min + under.metrics.ascent,
+                                         constants.lower_limit_baseline_drop_min))
+        };
+        left = cmp::min(left, x);
+        right = cmp::max(right, x + under.metrics.width);
+        descent = cmp::max(descent, under.metrics.descent - y);
+        Point::new(x, y)
+    });
+
+    let shift = |point: Point| Point::new(point.x - left, point.y);
+    UnderOverLayout {
+        base: shift(Point::new(0, 0)),
+        under: under.map(&shift),
+        over: over.map(&shift),
+        metrics: BoxMetrics::new(right - left, ascent, descent),
+    }
+}
+
+/// Glyph to use for `accent` over `base`. Bases taller than
+/// `flattened_accent_base_height` get the flattened form of the accent from the
+/// `flac` feature, if the font has one. Fails with `Error::MissingGlyph` if
+/// the font does not cover `accent`.
+pub fn accent_glyph(face: &HBFace, accent: char, base: &BoxMetrics) -> Result<u32, Error> {
+    let options = if base.ascent > face.math_constants().flattened_accent_base_height {
+        ShapeOptions::new().script("Zmth").feature(Feature::new("flac", 1))
+    } else {
+        ShapeOptions::new().script("Zmth")
+    };
+    let glyphs = face.shape(&[accent][..], &options)?;
+    match glyphs.first() {
+        Some(glyph) if glyph.glyph_index() != 0 => Ok(glyph.glyph_index()),
+        _ => Err(Error::MissingGlyph(accent)),
+    }
+}
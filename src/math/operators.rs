@@ -0,0 +1,402 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+//! MathML operator dictionary. Gives the spacing and stretching behaviour of
+//! an `mo` element from its character and form.
+//!
+//! The table is a partial copy of the MathML Core dictionary with 171 entries:
+//! ASCII operators and fences, arrows, relations, set and logic symbols, large
+//! operators and the common brackets. Operators it does not list get the
+//! default properties of `operator_properties`.
+
+use ::harfbuzz::{HBFace, HBDirection, HBGlyphExtents};
+use ::math::style::MathStyle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OperatorForm {
+    Prefix,
+    Infix,
+    Postfix,
+}
+
+const STRETCHY: u8 = 0x01;
+const SYMMETRIC: u8 = 0x02;
+const LARGEOP: u8 = 0x04;
+const MOVABLE_LIMITS: u8 = 0x08;
+const FENCE: u8 = 0x10;
+const SEPARATOR: u8 = 0x20;
+const ACCENT: u8 = 0x40;
+
+/// Dictionary entry of an operator. Spaces are in 1/18 em, the unit of the
+/// MathML named spaces: 3 is `thinmathspace` and 5 is `thickmathspace`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatorProperties {
+    form: OperatorForm,
+    lspace: i32,
+    rspace: i32,
+    flags: u8,
+}
+
+impl OperatorProperties {
+    /// Form of the entry, which differs from the requested form when it had
+    /// no entry of its own.
+    pub fn form(&self) -> OperatorForm {
+        self.form
+    }
+
+    pub fn lspace(&self) -> i32 {
+        self.lspace
+    }
+
+    pub fn rspace(&self) -> i32 {
+        self.rspace
+    }
+
+    pub fn is_stretchy(&self) -> bool {
+        self.flags & STRETCHY != 0
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.flags & SYMMETRIC != 0
+    }
+
+    pub fn is_large_op(&self) -> bool {
+        self.flags & LARGEOP != 0
+    }
+
+    pub fn has_movable_limits(&self) -> bool {
+        self.flags & MOVABLE_LIMITS != 0
+    }
+
+    pub fn is_fence(&self) -> bool {
+        self.flags & FENCE != 0
+    }
+
+    pub fn is_separator(&self) -> bool {
+        self.flags & SEPARATOR != 0
+    }
+
+    pub fn is_accent(&self) -> bool {
+        self.flags & ACCENT != 0
+    }
+}
+
+/// Dictionary entry for `ch` in exactly `form`.
+pub fn lookup(ch: char, form: OperatorForm) -> Option<OperatorProperties> {
+    OPERATORS.binary_search_by(|&(entry_ch, entry_form, _, _, _)| (entry_ch, entry_form).cmp(&(ch, form)))
+        .ok()
+        .map(|index| {
+            let (_, form, lspace, rspace, flags) = OPERATORS[index];
+            OperatorProperties { form, lspace, rspace, flags }
+        })
+}
+
+/// Every entry of the dictionary, ordered by character and form.
+pub fn dictionary() -> impl Iterator<Item = (char, OperatorProperties)> {
+    OPERATORS.iter().map(|&(ch, form, lspace, rspace, flags)| (ch, OperatorProperties { form, lspace, rspace, flags }))
+}
+
+/// Properties of `ch` used as an operator in `form`. Like MathML, falls back to
+/// the infix, postfix and prefix entries in that order, and to
+/// `thickmathspace` on both sides for operators missing from the dictionary.
+pub fn operator_properties(ch: char, form: OperatorForm) -> OperatorProperties {
+    lookup(ch, form)
+        .or_else(|| lookup(ch, OperatorForm::Infix))
+        .or_else(|| lookup(ch, OperatorForm::Postfix))
+        .or_else(|| lookup(ch, OperatorForm::Prefix))
+        .unwrap_or(OperatorProperties { form, lspace: 5, rspace: 5, flags: 0 })
+}
+
+/// Result of `HBFace::operator_glyph`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorGlyph {
+    glyph_index: u32,
+    properties: OperatorProperties,
+    lspace: i32,
+    rspace: i32,
+    is_extended_shape: bool,
+}
+
+impl OperatorGlyph {
+    pub fn glyph_index(&self) -> u32 {
+        self.glyph_index
+    }
+
+    pub fn properties(&self) -> OperatorProperties {
+        self.properties
+    }
+
+    /// Space before the operator in the units of the face.
+    pub fn lspace(&self) -> i32 {
+        self.lspace
+    }
+
+    /// Space after the operator in the units of the face.
+    pub fn rspace(&self) -> i32 {
+        self.rspace
+    }
+
+    /// Whether scripts should be attached as to a tall construction, because
+    /// the operator stretches or the font lists its glyph as an extended shape.
+    pub fn is_extended_shape(&self) -> bool {
+        self.is_extended_shape
+    }
+}
+
+/// Result of `HBFace::large_operator`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LargeOperator {
+    glyph_index: u32,
+    extents: HBGlyphExtents,
+    italics_correction: i32,
+}
+
+impl LargeOperator {
+    pub fn glyph_index(&self) -> u32 {
+        self.glyph_index
+    }
+
+    /// Ink box of the chosen glyph.
+    pub fn extents(&self) -> HBGlyphExtents {
+        self.extents
+    }
+
+    /// Italic correction of the chosen glyph. Integrals move their upper limit
+    /// or superscript right and their lower limit or subscript left by it, see
+    /// `UnderOverBox::italics_correction`.
+    pub fn italics_correction(&self) -> i32 {
+        self.italics_correction
+    }
+}
+
+impl HBFace {
+    /// Sizes the large operator `glyph_index` for `style`. In display style the
+    /// first vertical size variant at least `display_operator_min_height` tall
+    /// is used, or the largest one if none is. Other styles keep the glyph.
+    pub fn large_operator(&self, glyph_index: u32, style: MathStyle) -> LargeOperator {
+        let mut glyph_index = glyph_index;
+        if style.is_display() {
+            let min_height = self.display_operator_min_height();
+            let variants: Vec<_> = self.glyph_variants(glyph_index, HBDirection::TTB).collect();
+            let variant = variants.iter().find(|variant| variant.advance() >= min_height).or(variants.last());
+            if let Some(variant) = variant {
+                glyph_index = variant.glyph_index();
+            }
+        }
+
+        LargeOperator {
+            glyph_index,
+            extents: self.glyph_extents(glyph_index).unwrap_or_default(),
+            italics_correction: self.italics_correction(glyph_index),
+        }
+    }
+
+    /// Looks up the operator `ch` in `form` and picks its glyph, sized with
+    /// `large_operator` for large operators. Returns `None` if the font does
+    /// not cover `ch`.
+    pub fn operator_glyph(&self, ch: char, form: OperatorForm, style: MathStyle) -> Option<OperatorGlyph> {
+        let properties = operator_properties(ch, form);
+        let mut glyph_index = self.glyph_index(ch as u32)?;
+        if properties.is_large_op() {
+            glyph_index = self.large_operator(glyph_index, style).glyph_index();
+        }
+
+        let scale = self.scale();
+        Some(OperatorGlyph {
+            glyph_index,
+            properties,
+            lspace: properties.lspace * scale / 18,
+            rspace: properties.rspace * scale / 18,
+            is_extended_shape: properties.is_stretchy() || self.is_glyph_extended_shape(glyph_index),
+        })
+    }
+}
+
+// Sorted by character and form for binary search
+const OPERATORS: &'static [(char, OperatorForm, i32, i32, u8)] = &[
+    ('!', OperatorForm::Postfix, 1, 0, 0),
+    ('%', OperatorForm::Postfix, 0, 0, 0),
+    ('(', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    (')', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('*', OperatorForm::Infix, 4, 4, 0),
+    ('+', OperatorForm::Prefix, 0, 1, 0),
+    ('+', OperatorForm::Infix, 4, 4, 0),
+    (',', OperatorForm::Infix, 0, 3, SEPARATOR),
+    ('-', OperatorForm::Prefix, 0, 1, 0),
+    ('-', OperatorForm::Infix, 4, 4, 0),
+    ('/', OperatorForm::Infix, 4, 4, 0),
+    (':', OperatorForm::Infix, 1, 2, 0),
+    (';', OperatorForm::Infix, 0, 3, SEPARATOR),
+    ('<', OperatorForm::Infix, 5, 5, 0),
+    ('=', OperatorForm::Infix, 5, 5, 0),
+    ('>', OperatorForm::Infix, 5, 5, 0),
+    ('[', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    (']', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('^', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('_', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('`', OperatorForm::Postfix, 0, 0, ACCENT),
+    ('{', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('|', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('|', OperatorForm::Infix, 2, 2, FENCE | STRETCHY | SYMMETRIC),
+    ('|', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('~', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{A8}', OperatorForm::Postfix, 0, 0, ACCENT),
+    ('\u{AC}', OperatorForm::Prefix, 2, 1, 0),
+    ('\u{AF}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{B0}', OperatorForm::Postfix, 0, 0, 0),
+    ('\u{B1}', OperatorForm::Prefix, 0, 1, 0),
+    ('\u{B1}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{B4}', OperatorForm::Postfix, 0, 0, ACCENT),
+    ('\u{B7}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{B8}', OperatorForm::Postfix, 0, 0, ACCENT),
+    ('\u{D7}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{F7}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2C6}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{2C7}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{2D9}', OperatorForm::Postfix, 0, 0, ACCENT),
+    ('\u{2DA}', OperatorForm::Postfix, 0, 0, ACCENT),
+    ('\u{2DC}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{2016}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2016}', OperatorForm::Infix, 2, 2, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2016}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2032}', OperatorForm::Postfix, 0, 0, 0),
+    ('\u{2033}', OperatorForm::Postfix, 0, 0, 0),
+    ('\u{2034}', OperatorForm::Postfix, 0, 0, 0),
+    ('\u{203E}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{2061}', OperatorForm::Infix, 0, 0, 0),
+    ('\u{2062}', OperatorForm::Infix, 0, 0, 0),
+    ('\u{2063}', OperatorForm::Infix, 0, 0, SEPARATOR),
+    ('\u{2064}', OperatorForm::Infix, 0, 0, 0),
+    ('\u{2190}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2191}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2192}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2193}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2194}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2195}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21A6}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21A9}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21AA}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21CB}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21CC}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21D0}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21D1}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21D2}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21D3}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{21D4}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2200}', OperatorForm::Prefix, 2, 1, 0),
+    ('\u{2202}', OperatorForm::Prefix, 2, 1, 0),
+    ('\u{2203}', OperatorForm::Prefix, 2, 1, 0),
+    ('\u{2204}', OperatorForm::Prefix, 2, 1, 0),
+    ('\u{2207}', OperatorForm::Prefix, 2, 1, 0),
+    ('\u{2208}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2209}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{220B}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{220F}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2210}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2211}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2212}', OperatorForm::Prefix, 0, 1, 0),
+    ('\u{2212}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2213}', OperatorForm::Prefix, 0, 1, 0),
+    ('\u{2213}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2216}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2217}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2218}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2219}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{221A}', OperatorForm::Prefix, 1, 1, STRETCHY),
+    ('\u{221D}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2223}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2225}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2227}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2228}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2229}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{222A}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{222B}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{222C}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{222D}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{222E}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{222F}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{2230}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{2231}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{2232}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{2233}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{223C}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2243}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2245}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2248}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2254}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2260}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2261}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2264}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2265}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{226A}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{226B}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{227A}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{227B}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2282}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2283}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2286}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2287}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{2295}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2296}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2297}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2298}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2299}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{22A5}', OperatorForm::Infix, 5, 5, 0),
+    ('\u{22C0}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{22C1}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{22C2}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{22C3}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{22C5}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{22C6}', OperatorForm::Infix, 4, 4, 0),
+    ('\u{2308}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2309}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{230A}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{230B}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2329}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{232A}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{23B4}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{23B5}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{23DC}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{23DD}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{23DE}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{23DF}', OperatorForm::Postfix, 0, 0, ACCENT | STRETCHY),
+    ('\u{27E6}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27E7}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27E8}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27E9}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27EA}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27EB}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27EE}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27EF}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{27F5}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{27F6}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{27F7}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{27F8}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{27F9}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{27FA}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{27FC}', OperatorForm::Infix, 5, 5, STRETCHY),
+    ('\u{2983}', OperatorForm::Prefix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2984}', OperatorForm::Postfix, 0, 0, FENCE | STRETCHY | SYMMETRIC),
+    ('\u{2A00}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2A01}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2A02}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2A04}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2A06}', OperatorForm::Prefix, 1, 2, LARGEOP | MOVABLE_LIMITS | SYMMETRIC),
+    ('\u{2A0C}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+    ('\u{2A11}', OperatorForm::Prefix, 0, 1, LARGEOP | SYMMETRIC),
+];
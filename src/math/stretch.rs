@@ -0,0 +1,215 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::cmp;
+
+use ::harfbuzz::{HBFace, HBDirection, HBGlyphPart};
+
+/// A glyph of a stretched construction, placed along the stretch direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StretchedGlyphPart {
+    glyph_index: u32,
+    offset: i32,
+}
+
+impl StretchedGlyphPart {
+    pub fn glyph_index(&self) -> u32 {
+        self.glyph_index
+    }
+
+    /// Distance of the part from the start of the construction. Vertical
+    /// constructions start at the bottom and horizontal ones at the left.
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+}
+
+/// Result of `HBFace::stretch_glyph`. Either a single size variant or a list of
+/// glyph assembly parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StretchedGlyph {
+    parts: Vec<StretchedGlyphPart>,
+    advance: i32,
+    italics_correction: i32,
+    is_assembly: bool,
+}
+
+impl StretchedGlyph {
+    pub fn parts(&self) -> &Vec<StretchedGlyphPart> {
+        &self.parts
+    }
+
+    /// Total size of the construction along the stretch direction.
+    pub fn advance(&self) -> i32 {
+        self.advance
+    }
+
+    pub fn italics_correction(&self) -> i32 {
+        self.italics_correction
+    }
+
+    pub fn is_assembly(&self) -> bool {
+        self.is_assembly
+    }
+}
+
+impl HBFace {
+    /// Stretches `glyph_index` to cover `target_size` along `direction` following
+    /// the OpenType MATH rules. The first size variant that is at least as large
+    /// as the target is used. Otherwise the glyph assembly is built, repeating
+    /// extenders and spreading the connector overlaps evenly. If the font cannot
+    /// reach the target, the largest construction available is returned.
+    pub fn stretch_glyph(&self, glyph_index: u32, direction: HBDirection, target_size: i32) -> StretchedGlyph {
+        let mut largest = None;
+        for variant in self.glyph_variants(glyph_index, direction) {
+            if variant.advance() >= target_size {
+                return self.stretched_variant(variant.glyph_index(), variant.advance());
+            }
+            largest = Some((variant.glyph_index(), variant.advance()));
+        }
+
+        let assembly = self.glyph_assembly(glyph_index, direction);
+        if assembly.len() > 0 {
+            let min_overlap = if direction.is_vertical() {
+                self.min_connector_overlap_vertical()
+            } else {
+                self.min_connector_overlap_horizontal()
+            };
+            let stretched = assemble(assembly.parts(), min_overlap, target_size,
+                                     assembly.italics_correction());
+
+            let is_larger = largest.map_or(true, |(_, advance)| stretched.advance > advance);
+            if is_larger {
+                return stretched;
+            }
+        }
+
+        match largest {
+            Some((glyph_index, advance)) => self.stretched_variant(glyph_index, advance),
+            None => self.stretched_variant(glyph_index, self.glyph_size(glyph_index, direction))
+        }
+    }
+
+    fn stretched_variant(&self, glyph_index: u32, advance: i32) -> StretchedGlyph {
+        StretchedGlyph {
+            parts: vec![StretchedGlyphPart { glyph_index, offset: 0 }],
+            advance,
+            italics_correction: self.italics_correction(glyph_index),
+            is_assembly: false,
+        }
+    }
+}
+
+fn assemble(parts: &[HBGlyphPart], min_overlap: i32, target_size: i32, italics_correction: i32) -> StretchedGlyph {
+    let repeats = extender_repeats(parts, min_overlap, target_size);
+
+    let mut sequence = Vec::new();
+    for part in parts {
+        let count = if part.is_extender() { repeats } else { 1 };
+        for _ in 0..count {
+            sequence.push(part);
+        }
+    }
+
+    let overlaps = connector_overlaps(&sequence, min_overlap, target_size);
+
+    let mut stretched = Vec::with_capacity(sequence.len());
+    let mut offset = 0;
+    for (index, part) in sequence.iter().enumerate() {
+        stretched.push(StretchedGlyphPart { glyph_index: part.glyph_index(), offset });
+        offset += part.full_advance();
+        if index < overlaps.len() {
+            offset -= overlaps[index];
+        }
+    }
+
+    StretchedGlyph {
+        parts: stretched,
+        advance: offset,
+        italics_correction,
+        is_assembly: true,
+    }
+}
+
+/// Smallest number of times each extender must be repeated so that the
+/// assembly, with every connector overlapping by `min_overlap`, reaches
+/// `target_size`.
+fn extender_repeats(parts: &[HBGlyphPart], min_overlap: i32, target_size: i32) -> usize {
+    let mut base_size = 0;
+    let mut growth = 0;
+    let mut base_count = 0;
+    for part in parts {
+        if part.is_extender() {
+            growth += part.full_advance() - min_overlap;
+        } else {
+            base_size += part.full_advance();
+            base_count += 1;
+        }
+    }
+    base_size -= min_overlap * cmp::max(base_count - 1, 0);
+
+    if base_count == 0 {
+        // Without fixed parts the first repetition of the extenders has one
+        // joint less than the following ones.
+        base_size = min_overlap;
+    }
+
+    if base_size >= target_size || growth <= 0 {
+        return if base_count == 0 { 1 } else { 0 };
+    }
+
+    let missing = target_size - base_size;
+    ((missing + growth - 1) / growth) as usize
+}
+
+/// Overlap of each pair of adjacent parts in `sequence`. Every overlap is at
+/// least `min_overlap` and at most the shorter of the two connectors. Any extra
+/// length beyond `target_size` is absorbed by growing the overlaps evenly.
+fn connector_overlaps(sequence: &[&HBGlyphPart], min_overlap: i32, target_size: i32) -> Vec<i32> {
+    if sequence.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut limits = Vec::with_capacity(sequence.len() - 1);
+    for pair in sequence.windows(2) {
+        let connector = cmp::min(pair[0].end_connector_length(), pair[1].start_connector_length());
+        limits.push(cmp::max(connector, min_overlap));
+    }
+
+    let full_size: i32 = sequence.iter().map(|part| part.full_advance()).sum();
+    let mut overlaps = vec![min_overlap; limits.len()];
+    let mut remaining = full_size - target_size - min_overlap * limits.len() as i32;
+
+    while remaining > 0 {
+        let open: Vec<usize> = (0..limits.len()).filter(|&i| overlaps[i] < limits[i]).collect();
+        if open.is_empty() {
+            break;
+        }
+
+        let share = cmp::max(remaining / open.len() as i32, 1);
+        for i in open {
+            let grow = cmp::min(cmp::min(share, limits[i] - overlaps[i]), remaining);
+            overlaps[i] += grow;
+            remaining -= grow;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+
+    overlaps
+}
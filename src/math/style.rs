@@ -0,0 +1,123 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+/// TeX style level. Display and text style are both at script level 0, script
+/// style at level 1 and script script style at level 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleLevel {
+    Display,
+    Text,
+    Script,
+    ScriptScript,
+}
+
+/// Style a math sub-expression is laid out in. Cramped styles raise
+/// superscripts less, as in the denominator of a fraction or under a radical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MathStyle {
+    level: StyleLevel,
+    cramped: bool,
+}
+
+impl MathStyle {
+    pub fn new(level: StyleLevel, cramped: bool) -> MathStyle {
+        MathStyle { level, cramped }
+    }
+
+    pub fn display() -> MathStyle {
+        MathStyle::new(StyleLevel::Display, false)
+    }
+
+    pub fn text() -> MathStyle {
+        MathStyle::new(StyleLevel::Text, false)
+    }
+
+    pub fn script() -> MathStyle {
+        MathStyle::new(StyleLevel::Script, false)
+    }
+
+    pub fn script_script() -> MathStyle {
+        MathStyle::new(StyleLevel::ScriptScript, false)
+    }
+
+    /// Returns the cramped variant of this style.
+    pub fn cramped(self) -> MathStyle {
+        MathStyle::new(self.level, true)
+    }
+
+    pub fn level(&self) -> StyleLevel {
+        self.level
+    }
+
+    pub fn is_cramped(&self) -> bool {
+        self.cramped
+    }
+
+    pub fn is_display(&self) -> bool {
+        self.level == StyleLevel::Display
+    }
+
+    /// MathML `scriptlevel` of this style, between 0 and 2.
+    pub fn script_level(&self) -> u8 {
+        match self.level {
+            StyleLevel::Display | StyleLevel::Text => 0,
+            StyleLevel::Script => 1,
+            StyleLevel::ScriptScript => 2,
+        }
+    }
+
+    /// Style of the numerator of a fraction in this style.
+    pub fn numerator(&self) -> MathStyle {
+        MathStyle::new(self.smaller_level(), self.cramped)
+    }
+
+    /// Style of the denominator of a fraction in this style.
+    pub fn denominator(&self) -> MathStyle {
+        MathStyle::new(self.smaller_level(), true)
+    }
+
+    /// Style of a superscript attached in this style.
+    pub fn superscript(&self) -> MathStyle {
+        MathStyle::new(self.script_level_up(), self.cramped)
+    }
+
+    /// Style of a subscript attached in this style.
+    pub fn subscript(&self) -> MathStyle {
+        MathStyle::new(self.script_level_up(), true)
+    }
+
+    fn script_level_up(&self) -> StyleLevel {
+        match self.level {
+            StyleLevel::Display | StyleLevel::Text => StyleLevel::Script,
+            StyleLevel::Script | StyleLevel::ScriptScript => StyleLevel::ScriptScript,
+        }
+    }
+
+    fn smaller_level(&self) -> StyleLevel {
+        match self.level {
+            StyleLevel::Display => StyleLevel::Text,
+            StyleLevel::Text => StyleLevel::Script,
+            StyleLevel::Script | StyleLevel::ScriptScript => StyleLevel::ScriptScript,
+        }
+    }
+}
+
+impl Default for MathStyle {
+    fn default() -> MathStyle {
+        MathStyle::text()
+    }
+}
@@ -0,0 +1,267 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+//! Mapping between plain characters and the Mathematical Alphanumeric Symbols
+//! and Arabic Mathematical Alphabetic Symbols blocks, as selected by the MathML
+//! `mathvariant` attribute.
+
+use std::char;
+use std::str::FromStr;
+
+use ::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathVariant {
+    Normal,
+    Bold,
+    Italic,
+    BoldItalic,
+    DoubleStruck,
+    BoldFraktur,
+    Script,
+    BoldScript,
+    Fraktur,
+    SansSerif,
+    BoldSansSerif,
+    SansSerifItalic,
+    SansSerifBoldItalic,
+    Monospace,
+    Initial,
+    Tailed,
+    Looped,
+    Stretched,
+}
+
+impl MathVariant {
+    /// Returns the character `ch` is rendered as in this variant, or `ch`
+    /// itself if Unicode has no such character.
+    pub fn apply(&self, ch: char) -> char {
+        if *self == MathVariant::Normal {
+            return ch;
+        }
+
+        if let Some(&(_, _, mapped)) = LETTERLIKE.iter().find(|&&(variant, base, _)| variant == *self && base == ch) {
+            return mapped;
+        }
+
+        let mapped = match ch {
+            'A'..='Z' => latin(*self).map(|start| start + (ch as u32 - 'A' as u32)),
+            'a'..='z' => latin(*self).map(|start| start + 26 + (ch as u32 - 'a' as u32)),
+            '0'..='9' => digits(*self).map(|start| start + (ch as u32 - '0' as u32)),
+            '\u{131}' if *self == MathVariant::Italic => Some(0x1D6A4),
+            '\u{237}' if *self == MathVariant::Italic => Some(0x1D6A5),
+            '\u{3DC}' if *self == MathVariant::Bold => Some(0x1D7CA),
+            '\u{3DD}' if *self == MathVariant::Bold => Some(0x1D7CB),
+            _ => match (greek(*self), greek_index(ch)) {
+                (Some(start), Some(index)) => Some(start + index),
+                _ => arabic(*self, ch),
+            }
+        };
+
+        mapped.and_then(char::from_u32).unwrap_or(ch)
+    }
+
+    /// Splits a mathematical alphanumeric character into its plain character
+    /// and variant. Other characters are returned as is with `Normal`.
+    pub fn split(ch: char) -> (char, MathVariant) {
+        if let Some(&(variant, base, _)) = LETTERLIKE.iter().find(|&&(_, _, mapped)| mapped == ch) {
+            return (base, variant);
+        }
+
+        // Candidates are checked against `apply` so that the code points left
+        // unassigned in favour of Letterlike Symbols are not accepted
+        let code = ch as u32;
+        let candidate = match code {
+            0x1D400..=0x1D6A3 => {
+                let offset = code - 0x1D400;
+                let index = offset % 52;
+                let base = if index < 26 { 'A' as u32 + index } else { 'a' as u32 + index - 26 };
+                Some((base, LATIN[(offset / 52) as usize]))
+            },
+            0x1D6A4 => Some((0x131, MathVariant::Italic)),
+            0x1D6A5 => Some((0x237, MathVariant::Italic)),
+            0x1D6A8..=0x1D7C9 => {
+                let offset = code - 0x1D6A8;
+                Some((GREEK_CHARS[(offset % 58) as usize], GREEK[(offset / 58) as usize]))
+            },
+            0x1D7CA => Some((0x3DC, MathVariant::Bold)),
+            0x1D7CB => Some((0x3DD, MathVariant::Bold)),
+            0x1D7CE..=0x1D7FF => {
+                let offset = code - 0x1D7CE;
+                Some(('0' as u32 + offset % 10, DIGITS[(offset / 10) as usize]))
+            },
+            0x1EE20..=0x1EEBF => {
+                let offset = code - 0x1EE00;
+                ARABIC.iter()
+                    .find(|&&(_, start, _)| start == 0x1EE00 + offset / 32 * 32)
+                    .map(|&(variant, _, _)| (ARABIC_CHARS[(offset % 32) as usize], variant))
+            },
+            _ => None
+        };
+
+        candidate
+            .and_then(|(base, variant)| char::from_u32(base).map(|base| (base, variant)))
+            .filter(|&(base, variant)| variant.apply(base) == ch)
+            .unwrap_or((ch, MathVariant::Normal))
+    }
+}
+
+impl FromStr for MathVariant {
+    type Err = Error;
+
+    /// Parses a value of the MathML `mathvariant` attribute, for example
+    /// `bold-italic` or `double-struck`.
+    fn from_str(s: &str) -> Result<MathVariant, Error> {
+        Ok(match s {
+            "normal" => MathVariant::Normal,
+            "bold" => MathVariant::Bold,
+            "italic" => MathVariant::Italic,
+            "bold-italic" => MathVariant::BoldItalic,
+            "double-struck" => MathVariant::DoubleStruck,
+            "bold-fraktur" => MathVariant::BoldFraktur,
+            "script" => MathVariant::Script,
+            "bold-script" => MathVariant::BoldScript,
+            "fraktur" => MathVariant::Fraktur,
+            "sans-serif" => MathVariant::SansSerif,
+            "bold-sans-serif" => MathVariant::BoldSansSerif,
+            "sans-serif-italic" => MathVariant::SansSerifItalic,
+            "sans-serif-bold-italic" => MathVariant::SansSerifBoldItalic,
+            "monospace" => MathVariant::Monospace,
+            "initial" => MathVariant::Initial,
+            "tailed" => MathVariant::Tailed,
+            "looped" => MathVariant::Looped,
+            "stretched" => MathVariant::Stretched,
+            _ => return Err(Error::InvalidMathVariant(String::from(s)))
+        })
+    }
+}
+
+// Order of the Latin alphabets, 52 letters each, starting at U+1D400
+const LATIN: [MathVariant; 13] = [
+    MathVariant::Bold,
+    MathVariant::Italic,
+    MathVariant::BoldItalic,
+    MathVariant::Script,
+    MathVariant::BoldScript,
+    MathVariant::Fraktur,
+    MathVariant::DoubleStruck,
+    MathVariant::BoldFraktur,
+    MathVariant::SansSerif,
+    MathVariant::BoldSansSerif,
+    MathVariant::SansSerifItalic,
+    MathVariant::SansSerifBoldItalic,
+    MathVariant::Monospace,
+];
+
+// Order of the Greek alphabets, 58 characters each, starting at U+1D6A8
+const GREEK: [MathVariant; 5] = [
+    MathVariant::Bold,
+    MathVariant::Italic,
+    MathVariant::BoldItalic,
+    MathVariant::BoldSansSerif,
+    MathVariant::SansSerifBoldItalic,
+];
+
+// Characters of each Greek alphabet. U+03A2 is unassigned and takes the place
+// of U+03F4 GREEK CAPITAL THETA SYMBOL.
+const GREEK_CHARS: [u32; 58] = [
+    0x391, 0x392, 0x393, 0x394, 0x395, 0x396, 0x397, 0x398, 0x399, 0x39A, 0x39B, 0x39C, 0x39D,
+    0x39E, 0x39F, 0x3A0, 0x3A1, 0x3F4, 0x3A3, 0x3A4, 0x3A5, 0x3A6, 0x3A7, 0x3A8, 0x3A9, 0x2207,
+    0x3B1, 0x3B2, 0x3B3, 0x3B4, 0x3B5, 0x3B6, 0x3B7, 0x3B8, 0x3B9, 0x3BA, 0x3BB, 0x3BC, 0x3BD,
+    0x3BE, 0x3BF, 0x3C0, 0x3C1, 0x3C2, 0x3C3, 0x3C4, 0x3C5, 0x3C6, 0x3C7, 0x3C8, 0x3C9, 0x2202,
+    0x3F5, 0x3D1, 0x3F0, 0x3D5, 0x3F1, 0x3D6,
+];
+
+// Order of the digits, 10 each, starting at U+1D7CE
+const DIGITS: [MathVariant; 5] = [
+    MathVariant::Bold,
+    MathVariant::DoubleStruck,
+    MathVariant::SansSerif,
+    MathVariant::BoldSansSerif,
+    MathVariant::Monospace,
+];
+
+// Letters of each Arabic mathematical alphabet
+const ARABIC_CHARS: [u32; 32] = [
+    0x627, 0x628, 0x62C, 0x62F, 0x647, 0x648, 0x632, 0x62D, 0x637, 0x64A, 0x643, 0x644, 0x645,
+    0x646, 0x633, 0x639, 0x641, 0x635, 0x642, 0x631, 0x634, 0x62A, 0x62B, 0x62E, 0x630, 0x636,
+    0x638, 0x63A, 0x66E, 0x6BA, 0x6A1, 0x66F,
+];
+
+// Start of each Arabic alphabet and a bit set of the positions in
+// `ARABIC_CHARS` that are assigned
+const ARABIC: [(MathVariant, u32, u32); 5] = [
+    (MathVariant::Initial, 0x1EE20, 0b0000_1010_1111_0111_1111_1110_1001_0110),
+    (MathVariant::Tailed, 0x1EE40, 0b1010_1010_1001_0110_1110_1010_1000_0100),
+    (MathVariant::Stretched, 0x1EE60, 0b0101_1110_1111_0111_1111_0111_1001_0110),
+    (MathVariant::Looped, 0x1EE80, 0b0000_1111_1111_1111_1111_1011_1111_1111),
+    (MathVariant::DoubleStruck, 0x1EEA0, 0b0000_1111_1111_1111_1111_1011_1110_1110),
+];
+
+// Characters encoded in the Letterlike Symbols block instead of the
+// Mathematical Alphanumeric Symbols block
+const LETTERLIKE: [(MathVariant, char, char); 24] = [
+    (MathVariant::Italic, 'h', '\u{210E}'),
+    (MathVariant::Script, 'B', '\u{212C}'),
+    (MathVariant::Script, 'E', '\u{2130}'),
+    (MathVariant::Script, 'F', '\u{2131}'),
+    (MathVariant::Script, 'H', '\u{210B}'),
+    (MathVariant::Script, 'I', '\u{2110}'),
+    (MathVariant::Script, 'L', '\u{2112}'),
+    (MathVariant::Script, 'M', '\u{2133}'),
+    (MathVariant::Script, 'R', '\u{211B}'),
+    (MathVariant::Script, 'e', '\u{212F}'),
+    (MathVariant::Script, 'g', '\u{210A}'),
+    (MathVariant::Script, 'o', '\u{2134}'),
+    (MathVariant::Fraktur, 'C', '\u{212D}'),
+    (MathVariant::Fraktur, 'H', '\u{210C}'),
+    (MathVariant::Fraktur, 'I', '\u{2111}'),
+    (MathVariant::Fraktur, 'R', '\u{211C}'),
+    (MathVariant::Fraktur, 'Z', '\u{2128}'),
+    (MathVariant::DoubleStruck, 'C', '\u{2102}'),
+    (MathVariant::DoubleStruck, 'H', '\u{210D}'),
+    (MathVariant::DoubleStruck, 'N', '\u{2115}'),
+    (MathVariant::DoubleStruck, 'P', '\u{2119}'),
+    (MathVariant::DoubleStruck, 'Q', '\u{211A}'),
+    (MathVariant::DoubleStruck, 'R', '\u{211D}'),
+    (MathVariant::DoubleStruck, 'Z', '\u{2124}'),
+];
+
+fn latin(variant: MathVariant) -> Option<u32> {
+    LATIN.iter().position(|&v| v == variant).map(|index| 0x1D400 + 52 * index as u32)
+}
+
+fn greek(variant: MathVariant) -> Option<u32> {
+    GREEK.iter().position(|&v| v == variant).map(|index| 0x1D6A8 + 58 * index as u32)
+}
+
+fn greek_index(ch: char) -> Option<u32> {
+    GREEK_CHARS.iter().position(|&c| c == ch as u32).map(|index| index as u32)
+}
+
+fn digits(variant: MathVariant) -> Option<u32> {
+    DIGITS.iter().position(|&v| v == variant).map(|index| 0x1D7CE + 10 * index as u32)
+}
+
+fn arabic(variant: MathVariant, ch: char) -> Option<u32> {
+    let &(_, start, assigned) = ARABIC.iter().find(|&&(v, _, _)| v == variant)?;
+    let index = ARABIC_CHARS.iter().position(|&c| c == ch as u32)?;
+    if assigned & (1 << index) == 0 {
+        return None;
+    }
+    Some(start + index as u32)
+}
@@ -0,0 +1,88 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::fmt::Write;
+
+/// A drawing command of a glyph outline. Coordinates are in the units of the
+/// face the outline was read from, with y pointing up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutlineCommand {
+    MoveTo(i32, i32),
+    LineTo(i32, i32),
+    /// Quadratic Bézier curve with one control point.
+    QuadTo(i32, i32, i32, i32),
+    /// Cubic Bézier curve with two control points.
+    CubicTo(i32, i32, i32, i32, i32, i32),
+    /// Closes the current contour.
+    Close,
+}
+
+/// Outline of a glyph as a list of closed contours.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Outline {
+    commands: Vec<OutlineCommand>,
+}
+
+impl Outline {
+    pub fn new(commands: Vec<OutlineCommand>) -> Outline {
+        Outline { commands }
+    }
+
+    pub fn commands(&self) -> &Vec<OutlineCommand> {
+        &self.commands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Returns the outline multiplied by `scale`, for example `ppem / upem` to
+    /// go from font units to pixels. Coordinates are rounded.
+    pub fn scaled(&self, scale: f32) -> Outline {
+        let s = |value: i32| (value as f32 * scale).round() as i32;
+        Outline::new(self.commands.iter().map(|command| match *command {
+            OutlineCommand::MoveTo(x, y) => OutlineCommand::MoveTo(s(x), s(y)),
+            OutlineCommand::LineTo(x, y) => OutlineCommand::LineTo(s(x), s(y)),
+            OutlineCommand::QuadTo(cx, cy, x, y) => OutlineCommand::QuadTo(s(cx), s(cy), s(x), s(y)),
+            OutlineCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) =>
+                OutlineCommand::CubicTo(s(c1x), s(c1y), s(c2x), s(c2y), s(x), s(y)),
+            OutlineCommand::Close => OutlineCommand::Close,
+        }).collect())
+    }
+
+    /// Formats the outline as the `d` attribute of an SVG `path`. The y axis is
+    /// flipped, so the path draws the glyph with its origin at `(0, 0)` in SVG
+    /// coordinates.
+    pub fn to_svg_path(&self) -> String {
+        let mut path = String::new();
+        for command in &self.commands {
+            if !path.is_empty() {
+                path.push(' ');
+            }
+            // Writing to a String cannot fail
+            let _ = match *command {
+                OutlineCommand::MoveTo(x, y) => write!(path, "M{} {}", x, -y),
+                OutlineCommand::LineTo(x, y) => write!(path, "L{} {}", x, -y),
+                OutlineCommand::QuadTo(cx, cy, x, y) => write!(path, "Q{} {} {} {}", cx, -cy, x, -y),
+                OutlineCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) =>
+                    write!(path, "C{} {} {} {} {} {}", c1x, -c1y, c2x, -c2y, x, -y),
+                OutlineCommand::Close => write!(path, "Z"),
+            };
+        }
+        path
+    }
+}
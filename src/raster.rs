@@ -0,0 +1,134 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+/// How outlines are fitted to the pixel grid before rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hinting {
+    /// Keeps the outline as designed. Best with subpixel positioning.
+    None,
+    /// Only fits vertical metrics, keeping glyph shapes and advances. Mono
+    /// rendering always uses the hinting designed for it, so this acts like
+    /// `Normal` with `Antialias::Mono`.
+    Light,
+    /// Full hinting with the font's own instructions or the autohinter.
+    Normal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Antialias {
+    /// One bit per pixel, expanded to 0 or 255 coverage.
+    Mono,
+    /// One coverage byte per pixel.
+    Gray,
+    /// Three coverage bytes per pixel for the red, green and blue subpixels of
+    /// a horizontal RGB display.
+    Lcd,
+}
+
+/// Settings for `FreetypeFace::rasterize`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterOptions {
+    hinting: Hinting,
+    antialias: Antialias,
+}
+
+impl RasterOptions {
+    pub fn new() -> RasterOptions {
+        RasterOptions { hinting: Hinting::Light, antialias: Antialias::Gray }
+    }
+
+    pub fn hinting(mut self, hinting: Hinting) -> RasterOptions {
+        self.hinting = hinting;
+        self
+    }
+
+    pub fn antialias(mut self, antialias: Antialias) -> RasterOptions {
+        self.antialias = antialias;
+        self
+    }
+
+    pub fn get_hinting(&self) -> Hinting {
+        self.hinting
+    }
+
+    pub fn get_antialias(&self) -> Antialias {
+        self.antialias
+    }
+}
+
+impl Default for RasterOptions {
+    fn default() -> RasterOptions {
+        RasterOptions::new()
+    }
+}
+
+/// Coverage bitmap of a rendered glyph. Rows run from top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphBitmap {
+    width: u32,
+    height: u32,
+    pitch: usize,
+    left: i32,
+    top: i32,
+    antialias: Antialias,
+    data: Vec<u8>,
+}
+
+impl GlyphBitmap {
+    pub(crate) fn new(width: u32, height: u32, pitch: usize, left: i32, top: i32, antialias: Antialias,
+                      data: Vec<u8>) -> GlyphBitmap {
+        GlyphBitmap { width, height, pitch, left, top, antialias, data }
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of bytes per row of `data`.
+    pub fn pitch(&self) -> usize {
+        self.pitch
+    }
+
+    /// Horizontal distance from the pen position to the left of the bitmap.
+    pub fn left(&self) -> i32 {
+        self.left
+    }
+
+    /// Vertical distance from the baseline up to the top of the bitmap.
+    pub fn top(&self) -> i32 {
+        self.top
+    }
+
+    pub fn antialias(&self) -> Antialias {
+        self.antialias
+    }
+
+    /// Coverage values, one byte per pixel or three for `Antialias::Lcd`.
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
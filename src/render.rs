@@ -0,0 +1,236 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+//! Software compositor drawing shaped runs and laid out math into an RGBA
+//! buffer, without any windowing system or GPU.
+//!
+//! Canvas coordinates are in pixels with y pointing down. Metrics of a
+//! `FreetypeFace` are in 1/64 pixels at the current size.
+
+use std::cmp;
+
+use ::error::Error;
+use ::freetype::FreetypeFace;
+use ::harfbuzz::HBGlyphPositions;
+use ::math::layout::{MathBox, PositionedGlyph, Rule};
+use ::raster::{Antialias, GlyphBitmap, RasterOptions};
+
+/// Color with straight, not premultiplied, alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn black() -> Color {
+        Color::new(0, 0, 0, 255)
+    }
+
+    pub fn white() -> Color {
+        Color::new(255, 255, 255, 255)
+    }
+
+    pub fn transparent() -> Color {
+        Color::new(0, 0, 0, 0)
+    }
+}
+
+/// RGBA image, 4 bytes per pixel, rows from top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Canvas {
+    /// Creates a canvas filled with `background`.
+    pub fn new(width: u32, height: u32, background: Color) -> Canvas {
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for _ in 0..width as usize * height as usize {
+            data.extend_from_slice(&[background.r, background.g, background.b, background.a]);
+        }
+        Canvas { width, height, data }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        Color::new(self.data[index], self.data[index + 1], self.data[index + 2], self.data[index + 3])
+    }
+
+    /// Draws `glyph_index` with its origin at `(x, y)`. The fractional part of
+    /// the position is applied while rasterizing.
+    pub fn draw_glyph(&mut self, face: &FreetypeFace, glyph_index: u32, x: f32, y: f32, color: Color,
+                      options: &RasterOptions) -> Result<(), Error> {
+        self.draw_rasterized(x, y, color, |offset| face.rasterize(glyph_index, offset, options))
+    }
+
+    /// Draws glyphs whose positions are in the units of `face`, relative to the
+    /// pen position `(x, y)`. Glyphs laid out with a face of another scale,
+    /// like the script faces of `font_for_style`, are drawn at their own size.
+    pub fn draw_glyphs(&mut self, face: &FreetypeFace, glyphs: &[PositionedGlyph], x: f32, y: f32, color: Color,
+                       options: &RasterOptions) -> Result<(), Error> {
+        let face_scale = face.get_hb_face().scale();
+        for glyph in glyphs {
+            let scale = if glyph.scale > 0 { glyph.scale as f32 / face_scale as f32 } else { 1. };
+            self.draw_rasterized(x + to_pixels(glyph.x), y - to_pixels(glyph.y), color, |offset| {
+                face.rasterize_scaled(glyph.glyph_index, scale, offset, options)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Draws a run measured with `face` starting at the pen position `(x, y)`.
+    pub fn draw_run(&mut self, face: &FreetypeFace, positions: &HBGlyphPositions, x: f32, y: f32, color: Color,
+                    options: &RasterOptions) -> Result<(), Error> {
+        let (mut pen_x, mut pen_y) = (0, 0);
+        for position in positions.positions() {
+            self.draw_glyph(face, position.glyph_index(),
+                            x + to_pixels(pen_x + position.x_offset()),
+                            y - to_pixels(pen_y + position.y_offset()),
+                            color, options)?;
+            pen_x += position.x_advance();
+            pen_y += position.y_advance();
+        }
+        Ok(())
+    }
+
+    /// Draws the glyphs and rules of a math box laid out with `face`, with the
+    /// origin of the box at `(x, y)`.
+    pub fn draw_math_box(&mut self, face: &FreetypeFace, math_box: &MathBox, x: f32, y: f32, color: Color,
+                         options: &RasterOptions) -> Result<(), Error> {
+        self.draw_glyphs(face, &math_box.glyphs(), x, y, color, options)?;
+        for rule in math_box.rules() {
+            self.draw_rule(&rule, x, y, color);
+        }
+        Ok(())
+    }
+
+    /// Draws a rule given in the units of a face relative to the origin `(x, y)`.
+    pub fn draw_rule(&mut self, rule: &Rule, x: f32, y: f32, color: Color) {
+        self.fill_rect(x + to_pixels(rule.x), y - to_pixels(rule.y + rule.height),
+                       to_pixels(rule.width), to_pixels(rule.height), color);
+    }
+
+    /// Fills a rectangle given by its top left corner and size. Pixels
+    /// partially covered by the rectangle are blended by the covered area.
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        if width <= 0. || height <= 0. {
+            return;
+        }
+
+        let (right, bottom) = (x + width, y + height);
+        let first_x = cmp::max(x.floor() as i64, 0);
+        let first_y = cmp::max(y.floor() as i64, 0);
+        let last_x = cmp::min(right.ceil() as i64, self.width as i64);
+        let last_y = cmp::min(bottom.ceil() as i64, self.height as i64);
+        for pixel_y in first_y..last_y {
+            let cover_y = overlap(pixel_y as f32, y, bottom);
+            for pixel_x in first_x..last_x {
+                let coverage = cover_y * overlap(pixel_x as f32, x, right);
+                self.blend(pixel_x as u32, pixel_y as u32, color, [coverage; 3]);
+            }
+        }
+    }
+
+    // Blends the bitmap `rasterize` renders for the subpixel offset of the
+    // origin `(x, y)`
+    fn draw_rasterized<F>(&mut self, x: f32, y: f32, color: Color, rasterize: F) -> Result<(), Error>
+        where F: FnOnce((f32, f32)) -> Result<GlyphBitmap, Error> {
+        let (pixel_x, pixel_y) = (x.floor(), y.floor());
+        let bitmap = rasterize((x - pixel_x, pixel_y - y))?;
+        self.blend_bitmap(&bitmap, pixel_x as i32 + bitmap.left(), pixel_y as i32 - bitmap.top(), color);
+        Ok(())
+    }
+
+    fn blend_bitmap(&mut self, bitmap: &GlyphBitmap, left: i32, top: i32, color: Color) {
+        let data = bitmap.data();
+        for row in 0..bitmap.height() as i64 {
+            let y = top as i64 + row;
+            if y < 0 || y >= self.height as i64 {
+                continue;
+            }
+            for column in 0..bitmap.width() as i64 {
+                let x = left as i64 + column;
+                if x < 0 || x >= self.width as i64 {
+                    continue;
+                }
+                let offset = row as usize * bitmap.pitch();
+                let coverage = match bitmap.antialias() {
+                    Antialias::Lcd => {
+                        let index = offset + column as usize * 3;
+                        [data[index] as f32 / 255., data[index + 1] as f32 / 255., data[index + 2] as f32 / 255.]
+                    },
+                    Antialias::Mono | Antialias::Gray => [data[offset + column as usize] as f32 / 255.; 3],
+                };
+                self.blend(x as u32, y as u32, color, coverage);
+            }
+        }
+    }
+
+    // Source over compositing of `color` scaled by the coverage of each channel
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: [f32; 3]) {
+        if coverage.iter().all(|&c| c <= 0.) {
+            return;
+        }
+
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let source = [color.r, color.g, color.b];
+        let color_alpha = color.a as f32 / 255.;
+        let dest_alpha = self.data[index + 3] as f32 / 255.;
+        let mut out_alpha = 0f32;
+        for channel in 0..3 {
+            let alpha = color_alpha * coverage[channel];
+            let channel_alpha = alpha + dest_alpha * (1. - alpha);
+            if channel_alpha > 0. {
+                let blended = (source[channel] as f32 * alpha
+                    + self.data[index + channel] as f32 * dest_alpha * (1. - alpha)) / channel_alpha;
+                self.data[index + channel] = blended.round() as u8;
+            }
+            out_alpha = out_alpha.max(channel_alpha);
+        }
+        self.data[index + 3] = (out_alpha * 255.).round() as u8;
+    }
+}
+
+fn to_pixels(value: i32) -> f32 {
+    value as f32 / 64.
+}
+
+// Length of the part of the pixel starting at `pixel` covered by `start..end`
+fn overlap(pixel: f32, start: f32, end: f32) -> f32 {
+    (end.min(pixel + 1.) - start.max(pixel)).max(0.)
+}
@@ -0,0 +1,225 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+use std::str::FromStr;
+use std::u32;
+use std::i32;
+
+use ::harfbuzz_sys;
+use ::harfbuzz::HBDirection;
+use ::error::Error;
+
+/// Converts a four letter OpenType tag to its numeric form. Shorter tags are
+/// padded with spaces and longer ones truncated.
+pub fn tag(name: &str) -> u32 {
+    let mut bytes = [b' '; 4];
+    for (byte, c) in bytes.iter_mut().zip(name.bytes()) {
+        *byte = c;
+    }
+    ((bytes[0] as u32) << 24) | ((bytes[1] as u32) << 16) | ((bytes[2] as u32) << 8) | (bytes[3] as u32)
+}
+
+/// An OpenType feature applied to a range of the input, in clusters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Feature {
+    tag: u32,
+    value: u32,
+    start: u32,
+    end: u32,
+}
+
+impl Feature {
+    /// Feature applied to the whole input.
+    pub fn new(name: &str, value: u32) -> Feature {
+        Feature::with_range(name, value, 0, u32::MAX)
+    }
+
+    pub fn with_range(name: &str, value: u32, start: u32, end: u32) -> Feature {
+        Feature { tag: tag(name), value, start, end }
+    }
+
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+
+    pub(crate) fn to_hb_feature(&self) -> harfbuzz_sys::hb_feature_t {
+        harfbuzz_sys::hb_feature_t {
+            tag: self.tag,
+            value: self.value,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+impl FromStr for Feature {
+    type Err = Error;
+
+    /// Parses the syntax accepted by `hb_feature_from_string`, for example
+    /// `ssty=2`, `-liga` or `kern[3:5]=0`.
+    fn from_str(s: &str) -> Result<Feature, Error> {
+        let mut feature = Feature::new("", 0).to_hb_feature();
+        let parsed = unsafe {
+            harfbuzz_sys::hb_feature_from_string(s.as_ptr() as *const _, s.len() as i32, &mut feature)
+        };
+
+        if parsed == 0 {
+            return Err(Error::InvalidFeature(String::from(s)));
+        }
+
+        Ok(Feature {
+            tag: feature.tag,
+            value: feature.value,
+            start: feature.start,
+            end: feature.end,
+        })
+    }
+}
+
+/// Settings passed to the shaper. Anything not set explicitly is guessed from
+/// the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeOptions {
+    direction: Option<HBDirection>,
+    script: Option<String>,
+    language: Option<String>,
+    features: Vec<Feature>,
+}
+
+impl ShapeOptions {
+    pub fn new() -> ShapeOptions {
+        ShapeOptions {
+            direction: None,
+            script: None,
+            language: None,
+            features: Vec::new(),
+        }
+    }
+
+    pub fn direction(mut self, direction: HBDirection) -> ShapeOptions {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// ISO 15924 script tag such as `Latn` or `Arab`.
+    pub fn script(mut self, script: &str) -> ShapeOptions {
+        self.script = Some(String::from(script));
+        self
+    }
+
+    /// BCP 47 language tag such as `en` or `hi`.
+    pub fn language(mut self, language: &str) -> ShapeOptions {
+        self.language = Some(String::from(language));
+        self
+    }
+
+    pub fn feature(mut self, feature: Feature) -> ShapeOptions {
+        self.features.push(feature);
+        self
+    }
+
+    pub fn features(mut self, features: &[Feature]) -> ShapeOptions {
+        self.features.extend_from_slice(features);
+        self
+    }
+
+    pub fn get_direction(&self) -> Option<HBDirection> {
+        self.direction
+    }
+
+    pub fn get_script(&self) -> Option<&str> {
+        self.script.as_ref().map(|script| script.as_str())
+    }
+
+    pub fn get_language(&self) -> Option<&str> {
+        self.language.as_ref().map(|language| language.as_str())
+    }
+
+    pub fn get_features(&self) -> &Vec<Feature> {
+        &self.features
+    }
+}
+
+impl Default for ShapeOptions {
+    fn default() -> ShapeOptions {
+        ShapeOptions::new()
+    }
+}
+
+impl From<HBDirection> for ShapeOptions {
+    fn from(direction: HBDirection) -> ShapeOptions {
+        ShapeOptions::new().direction(direction)
+    }
+}
+
+/// Text accepted by `HBFace::measure` and `HBFace::shape`. Glyph clusters are
+/// offsets in the code units of the input: bytes for UTF-8 strings, code units
+/// for UTF-16 slices and characters for `char` slices.
+pub trait ShapeText {
+    #[doc(hidden)]
+    fn add_to_buffer(&self, buffer: *mut harfbuzz_sys::hb_buffer_t) -> Result<(), Error>;
+}
+
+fn text_length(len: usize) -> Result<i32, Error> {
+    if len > i32::MAX as usize {
+        return Err(Error::InvalidText);
+    }
+    Ok(len as i32)
+}
+
+impl ShapeText for str {
+    fn add_to_buffer(&self, buffer: *mut harfbuzz_sys::hb_buffer_t) -> Result<(), Error> {
+        let len = text_length(self.len())?;
+        unsafe { harfbuzz_sys::hb_buffer_add_utf8(buffer, self.as_ptr() as *const _, len, 0, len) };
+        Ok(())
+    }
+}
+
+impl ShapeText for String {
+    fn add_to_buffer(&self, buffer: *mut harfbuzz_sys::hb_buffer_t) -> Result<(), Error> {
+        self.as_str().add_to_buffer(buffer)
+    }
+}
+
+impl ShapeText for [u16] {
+    fn add_to_buffer(&self, buffer: *mut harfbuzz_sys::hb_buffer_t) -> Result<(), Error> {
+        let len = text_length(self.len())?;
+        unsafe { harfbuzz_sys::hb_buffer_add_utf16(buffer, self.as_ptr(), len, 0, len) };
+        Ok(())
+    }
+}
+
+impl ShapeText for [char] {
+    fn add_to_buffer(&self, buffer: *mut harfbuzz_sys::hb_buffer_t) -> Result<(), Error> {
+        let len = text_length(self.len())?;
+        // char has the same layout as a UTF-32 code unit
+        unsafe { harfbuzz_sys::hb_buffer_add_utf32(buffer, self.as_ptr() as *const u32, len, 0, len) };
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+extern crate akriti_measure;
+
+use std::sync::Arc;
+use std::thread;
+
+use akriti_measure::freetype::*;
+use akriti_measure::harfbuzz::HBFace;
+use akriti_measure::math::style::MathStyle;
+use akriti_measure::shaping::ShapeOptions;
+use akriti_measure::error::Error;
+
+static STIX2_MATH: &'static [u8] = include_bytes!("fonts/STIX2Math.otf");
+
+#[test]
+fn it_opens_font_from_static_memory() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap();
+    let face = face.get_hb_face();
+
+    assert_eq!(face.glyph_count(), 5248);
+    assert_eq!(face.upem(), 1000);
+    assert_eq!(face.has_ot_math_table(), true);
+}
+
+#[test]
+fn it_opens_font_from_owned_memory() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_memory(library, STIX2_MATH.to_vec(), 0).unwrap();
+
+    assert_eq!(face.get_hb_face().glyph_count(), 5248);
+}
+
+#[test]
+fn it_fails_to_open_invalid_memory_font() {
+    let library = Arc::new(Freetype::new().unwrap());
+    assert_eq!(FreetypeFace::new_from_memory(library, vec![0u8; 16], 0).err(), Some(Error::InvalidFontData));
+}
+
+#[test]
+fn it_fails_to_open_corrupted_font() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_file(library, &font_path("Corrupted.otf"), 0);
+
+    assert_eq!(face.err(), Some(Error::InvalidFontData));
+}
+
+#[test]
+fn it_fails_to_open_missing_face_index() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_file(library, &font_path("STIX2Math.otf"), 3);
+
+    assert_eq!(face.err(), Some(Error::InvalidFaceIndex(3)));
+}
+
+#[test]
+fn it_fails_to_open_missing_file() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_file(library, &font_path("Missing.otf"), 0);
+
+    assert_eq!(face.err(), Some(Error::FreeType(0x01)));
+}
+
+#[test]
+fn it_fails_to_open_path_with_nul() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_file(library, "STIX2\0Math.otf", 0);
+
+    assert_eq!(face.err(), Some(Error::InvalidPath("STIX2\0Math.otf".to_string())));
+}
+
+#[test]
+fn it_opens_bitmap_only_faces() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_file(library, &font_path("Fixed16.bdf"), 0).unwrap();
+    let face = face.get_hb_face();
+
+    // The only strike stays selected and metrics are in 1/64 pixels
+    assert_eq!(face.ppem(), Some(16.));
+    assert_eq!(face.scale(), 1024);
+    assert!(face.glyph_index('A' as u32).is_some());
+}
+
+fn font_path(name: &str) -> String {
+    format!("{}/tests/fonts/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn it_starts_in_font_units() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap();
+    let face = face.get_hb_face();
+
+    assert_eq!(face.ppem(), None);
+    assert_eq!(face.scale(), 1000);
+    assert_eq!(face.axis_height(), 258);
+    assert_eq!(face.fraction_rule_thickness(), 68);
+    assert_eq!(face.to_pixels(258), None);
+}
+
+#[test]
+fn it_scales_metrics_to_size() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let mut face = FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap();
+
+    face.set_size_pixels(0, 20).unwrap();
+    {
+        let face = face.get_hb_face();
+        assert_eq!(face.ppem(), Some(20.));
+        assert_eq!(face.scale(), 1280);
+        assert_eq!(face.axis_height(), 330);
+        assert_eq!(face.math_constants().axis_height, 330);
+        assert_eq!(face.to_pixels(1280), Some(20.));
+        assert_eq!(face.font_units().axis_height(), 258);
+        assert_eq!(face.font_units().scale(), 1000);
+    }
+
+    face.get_hb_face_mut().set_size_points(12., 96.).unwrap();
+    let face = face.get_hb_face();
+    assert_eq!(face.ppem(), Some(16.));
+    assert_eq!(face.scale(), 1024);
+    assert_eq!(face.math_constants().axis_height, face.axis_height());
+    assert_eq!(face.font_units().math_constants().axis_height, 258);
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn it_shares_faces_across_threads() {
+    assert_send_sync::<Freetype>();
+    assert_send_sync::<FreetypeFace>();
+    assert_send_sync::<HBFace>();
+
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = Arc::new(FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap());
+    let expected = face.get_hb_face().measure("Test", &ShapeOptions::new()).unwrap().width();
+
+    let threads: Vec<_> = (0..4).map(|_| {
+        let face = face.clone();
+        thread::spawn(move || {
+            let units = face.get_hb_face().font_units();
+            (0..50).map(|_| units.measure("Test", &ShapeOptions::new()).unwrap().width())
+                .collect::<Vec<_>>()
+        })
+    }).collect();
+
+    for thread in threads {
+        for width in thread.join().unwrap() {
+            assert_eq!(width, expected);
+        }
+    }
+}
+
+#[test]
+fn it_keeps_derived_faces_alive() {
+    let library = Arc::new(Freetype::new().unwrap());
+    let face = FreetypeFace::new_from_memory(library, STIX2_MATH.to_vec(), 0).unwrap();
+    let units = face.get_hb_face().font_units();
+    drop(face);
+
+    assert_eq!(units.axis_height(), 258);
+    assert_eq!(units.glyph_count(), 5248);
+}
+
+#[test]
+fn it_releases_derived_faces_while_opening_faces() {
+    let library = Arc::new(Freetype::new().unwrap());
+
+    let threads: Vec<_> = (0..4).map(|_| {
+        let library = library.clone();
+        thread::spawn(move || {
+            for _ in 0..25 {
+                let face = FreetypeFace::new_from_memory(library.clone(), STIX2_MATH, 0).unwrap();
+                let units = face.get_hb_face().font_units();
+                let script = face.get_hb_face().font_for_style(MathStyle::script());
+                drop(face);
+
+                // The derived faces release the last reference on the FT_Face
+                let dropper = thread::spawn(move || {
+                    assert_eq!(units.glyph_count(), 5248);
+                    assert_eq!(script.script_level(), 1);
+                    drop(units);
+                    drop(script);
+                });
+                let other = FreetypeFace::new_from_memory(library.clone(), STIX2_MATH, 0).unwrap();
+                assert_eq!(other.get_hb_face().glyph_count(), 5248);
+                dropper.join().unwrap();
+            }
+        })
+    }).collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+fn it_reads_glyph_outlines() {
+    use akriti_measure::outline::OutlineCommand;
+
+    let library = Arc::new(Freetype::new().unwrap());
+    let mut face = FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap();
+    let o = face.get_hb_face().glyph_index('o' as u32).unwrap();
+
+    let outline = face.glyph_outline(o).unwrap();
+    let commands = outline.commands();
+    let contours = commands.iter().filter(|command| match **command {
+        OutlineCommand::MoveTo(..) => true,
+        _ => false
+    }).count();
+    assert_eq!(contours, 2);
+    assert_eq!(commands.iter().filter(|&&command| command == OutlineCommand::Close).count(), 2);
+    assert_eq!(commands.last(), Some(&OutlineCommand::Close));
+
+    let path = outline.to_svg_path();
+    assert!(path.starts_with("M"));
+    assert!(path.ends_with("Z"));
+
+    let top = |commands: &Vec<OutlineCommand>| commands.iter().filter_map(|command| match *command {
+        OutlineCommand::MoveTo(_, y) | OutlineCommand::LineTo(_, y) |
+        OutlineCommand::QuadTo(_, _, _, y) | OutlineCommand::CubicTo(_, _, _, _, _, y) => Some(y),
+        OutlineCommand::Close => None,
+    }).max().unwrap();
+    // Top of the o, around the x-height
+    assert!(top(commands) > 400 && top(commands) < 550);
+
+    face.set_size_pixels(0, 20).unwrap();
+    let sized = face.glyph_outline(o).unwrap();
+    assert!((top(sized.commands()) as f32 - top(commands) as f32 * 1.28).abs() <= 2.);
+    assert_eq!(outline.scaled(1.28).commands().len(), sized.commands().len());
+
+    let space = face.get_hb_face().glyph_index(' ' as u32).unwrap();
+    assert!(face.glyph_outline(space).unwrap().is_empty());
+}
+
+#[test]
+fn it_rasterizes_glyphs() {
+    use akriti_measure::raster::*;
+
+    let library = Arc::new(Freetype::new().unwrap());
+    let mut face = FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap();
+    face.set_size_pixels(0, 20).unwrap();
+    let o = face.get_hb_face().glyph_index('o' as u32).unwrap();
+
+    let gray = face.rasterize(o, (0., 0.), &RasterOptions::new()).unwrap();
+    assert_eq!(gray.antialias(), Antialias::Gray);
+    assert!(gray.width() > 5 && gray.height() > 5);
+    assert!(gray.top() > 5 && gray.top() < 15);
+    assert_eq!(gray.pitch(), gray.width() as usize);
+    assert_eq!(gray.data().len(), gray.pitch() * gray.height() as usize);
+    assert!(gray.data().iter().any(|&coverage| coverage > 0 && coverage < 255));
+
+    let mono = face.rasterize(o, (0., 0.), &RasterOptions::new().antialias(Antialias::Mono)
+        .hinting(Hinting::Normal)).unwrap();
+    assert_eq!(mono.antialias(), Antialias::Mono);
+    assert_eq!(mono.data().len(), mono.width() as usize * mono.height() as usize);
+    assert!(mono.data().iter().all(|&coverage| coverage == 0 || coverage == 255));
+    assert!(mono.data().contains(&255));
+
+    let lcd = face.rasterize(o, (0., 0.), &RasterOptions::new().antialias(Antialias::Lcd)).unwrap();
+    assert_eq!(lcd.antialias(), Antialias::Lcd);
+    assert_eq!(lcd.pitch(), lcd.width() as usize * 3);
+    assert_eq!(lcd.data().len(), lcd.pitch() * lcd.height() as usize);
+    let lcd_hinted = face.rasterize(o, (0., 0.), &RasterOptions::new().antialias(Antialias::Lcd)
+        .hinting(Hinting::Normal)).unwrap();
+    assert_eq!(lcd_hinted.antialias(), Antialias::Lcd);
+
+    let unhinted = RasterOptions::new().hinting(Hinting::None);
+    let shifted = face.rasterize(o, (0.5, 0.), &unhinted).unwrap();
+    assert!(shifted.data() != face.rasterize(o, (0., 0.), &unhinted).unwrap().data());
+
+    let space = face.get_hb_face().glyph_index(' ' as u32).unwrap();
+    assert!(face.rasterize(space, (0., 0.), &RasterOptions::new()).unwrap().is_empty());
+}
@@ -22,6 +22,7 @@ use core_foundation::string::{CFString, CFStringRef};
 use core_foundation::base::TCFType;
 
 use akriti_measure::harfbuzz::*;
+use akriti_measure::error::Error;
 
 #[link(name = "CoreGraphics", kind = "framework")]
 extern {
@@ -29,12 +30,11 @@ extern {
 }
 
 #[test]
-#[should_panic]
 fn it_fails_to_open_invalid_font() {
     let font_name = CFString::from_static_string("Invalid Font");
     let cg_font = unsafe { CGFontCreateWithFontName(font_name.as_concrete_TypeRef()) };
     
-    HBFace::from_cg_font(cg_font);
+    assert_eq!(HBFace::from_cg_font(cg_font).err(), Some(Error::InvalidFontData));
 }
 
 #[test]
@@ -42,7 +42,7 @@ fn it_opens_font() {
     let font_name = CFString::from_static_string("STIX Two Math");
     let cg_font = unsafe { CGFontCreateWithFontName(font_name.as_concrete_TypeRef()) };
     
-    let face = HBFace::from_cg_font(cg_font);
+    let face = HBFace::from_cg_font(cg_font).unwrap();
 
     assert_eq!(face.index(), 0);
     assert_eq!(face.glyph_count(), 5248);
@@ -113,6 +113,15 @@ fn it_opens_font() {
 
     assert_eq!(face.min_connector_overlap_vertical(), 100);
     assert_eq!(face.min_connector_overlap_horizontal(), 100);
+
+    let constants = face.math_constants();
+    assert_eq!(constants.script_percent_scale_down, 70);
+    assert_eq!(constants.display_operator_min_height, 1800);
+    assert_eq!(constants.axis_height, 258);
+    assert_eq!(constants.fraction_rule_thickness, 68);
+    assert_eq!(constants.radical_kern_after_degree, -335);
+    assert_eq!(constants.radical_degree_bottom_raise_percent, 55);
+    assert_eq!(constants.scaled(0.015).axis_height, 258. * 0.015);
 }
 
 #[test]
@@ -120,7 +129,7 @@ fn it_opens_arial_font() {
     let font_name = CFString::from_static_string("Arial");
     let cg_font = unsafe { CGFontCreateWithFontName(font_name.as_concrete_TypeRef()) };
     
-    let face = HBFace::from_cg_font(cg_font);
+    let face = HBFace::from_cg_font(cg_font).unwrap();
     assert_eq!(face.has_ot_math_table(), false);
     assert_eq!(face.upem(), 2048);
 }
\ No newline at end of file
@@ -22,17 +22,19 @@ use std::ptr;
 use std::ffi;
 
 use akriti_measure::harfbuzz::*;
+use akriti_measure::error::Error;
+use akriti_measure::info::*;
+use akriti_measure::shaping::*;
 
 #[test]
-#[should_panic]
 fn it_fails_to_open_invalid_font() {
-    HBFace::from_freetype_font(ptr::null_mut());
+    assert_eq!(HBFace::from_freetype_font(ptr::null_mut()).err(), Some(Error::InvalidFontData));
 }
 
 #[test]
 fn it_opens_freetype_font() {
     let face = open_ft_font("STIX2Math.otf") as FTFontRef;
-    let face = HBFace::from_freetype_font(face);
+    let face = HBFace::from_freetype_font(face).unwrap();
 
     assert_eq!(face.index(), 0);
     assert_eq!(face.glyph_count(), 5248);
@@ -82,7 +84,7 @@ fn it_opens_freetype_font() {
     assert_eq!(face.ascent(), 768);
     assert_eq!(face.descent(), -256);
 
-    let positions = face.measure(String::from("Test"), &HBDirection::LTR);
+    let positions = face.measure("Test", &ShapeOptions::new().direction(HBDirection::LTR)).unwrap();
     assert_eq!(positions.width(), 1638);
     assert_eq!(positions.height(), 0);
 }
@@ -90,12 +92,247 @@ fn it_opens_freetype_font() {
 #[test]
 fn it_read_metrics_correctly() {
     let face = open_ft_font("STIX2Math.otf") as FTFontRef;
-    let face = HBFace::from_freetype_font(face);
+    let face = HBFace::from_freetype_font(face).unwrap();
 
     assert_eq!(face.script_percent_scale_down(), 70);
     assert_eq!(face.script_script_percent_scale_down(), 55);
 }
 
+#[test]
+fn it_reads_font_extents() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+
+    let extents = face.h_extents();
+    assert_eq!(extents.ascender(), face.ascent());
+    assert_eq!(extents.descender(), face.descent());
+    assert_eq!(extents.line_gap(), face.line_gap());
+    assert!(extents.line_gap() >= 0);
+    assert_eq!(extents.line_height(), extents.ascender() - extents.descender() + extents.line_gap());
+    assert_eq!(face.extents(HBDirection::LTR), extents);
+
+    // STIX2 has no vhea table
+    assert_eq!(face.v_extents(), FontExtents::default());
+    assert_eq!(face.v_extents().line_height(), 0);
+    assert_eq!(face.extents(HBDirection::TTB), FontExtents::default());
+}
+
+#[test]
+#[cfg(feature = "harfbuzz-2-6")]
+fn it_reads_font_metrics() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+
+    let x_height = face.x_height().unwrap();
+    let cap_height = face.cap_height().unwrap();
+    assert!(x_height > 0 && x_height < cap_height);
+    assert!(cap_height < face.ascent());
+
+    assert!(face.metric(FontMetric::UnderlinePosition).unwrap() < 0);
+    assert!(face.metric(FontMetric::UnderlineThickness).unwrap() > 0);
+    assert!(face.metric(FontMetric::StrikeoutPosition).unwrap() > 0);
+    assert!(face.metric(FontMetric::StrikeoutThickness).unwrap() > 0);
+    assert!(face.metric(FontMetric::SuperscriptEmYOffset).unwrap() > 0);
+    assert!(face.metric(FontMetric::SubscriptEmYOffset).unwrap() > 0);
+}
+
+#[test]
+#[cfg(feature = "harfbuzz-2-6")]
+fn it_reads_font_info() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+
+    let info = FontInfo::read(&face);
+    assert_eq!(info.family(), Some("STIX Two Math"));
+    assert_eq!(info.subfamily(), Some("Regular"));
+    assert_eq!(info.full_name(), Some("STIX Two Math"));
+    assert_eq!(info.postscript_name(), Some("STIXTwoMath"));
+    assert_eq!(info.version(), Some("Version 2.00 b137"));
+    assert_eq!(info.font_revision(), 2.);
+    assert_eq!(info.weight_class(), 400);
+    assert_eq!(info.width_class(), 5);
+    assert!(!info.is_italic());
+    assert_eq!(info.embedding().usage(), EmbeddingUsage::Installable);
+    assert!(info.embedding().allows_subsetting());
+    assert_eq!(info.created(), Some(1454036424));
+    assert_eq!(info.modified(), Some(1480465637));
+    assert_eq!(face.name(9000), None);
+
+    let permissions = EmbeddingPermissions::new(0x0306);
+    assert_eq!(permissions.usage(), EmbeddingUsage::PreviewAndPrint);
+    assert!(!permissions.allows_subsetting());
+    assert!(permissions.is_bitmap_only());
+}
+
+#[test]
+fn it_reads_math_constants_snapshot() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+    let constants = face.math_constants();
+
+    assert_eq!(constants.script_percent_scale_down, 70);
+    assert_eq!(constants.script_script_percent_scale_down, 55);
+    assert_eq!(constants.axis_height, face.axis_height());
+    assert_eq!(constants.fraction_rule_thickness, face.fraction_rule_thickness());
+    assert_eq!(constants.radical_kern_after_degree, face.radical_kern_after_degree());
+    assert_eq!(constants.radical_degree_bottom_raise_percent, face.radical_degree_bottom_raise_percent());
+
+    let scaled = constants.scaled(0.5);
+    assert_eq!(scaled.script_percent_scale_down, 70);
+    assert_eq!(scaled.axis_height, constants.axis_height as f32 * 0.5);
+}
+
+#[test]
+fn it_stretches_glyphs() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+    let radical = face.glyph_index('√' as u32).unwrap();
+
+    let small = face.stretch_glyph(radical, HBDirection::TTB, 1);
+    assert_eq!(small.is_assembly(), false);
+    assert_eq!(small.parts().len(), 1);
+    assert_eq!(small.parts()[0].glyph_index(), 1657);
+
+    let largest = face.glyph_variants(radical, HBDirection::TTB).last().unwrap();
+    let medium = face.stretch_glyph(radical, HBDirection::TTB, largest.advance());
+    assert_eq!(medium.is_assembly(), false);
+    assert_eq!(medium.parts()[0].glyph_index(), 1660);
+    assert_eq!(medium.advance(), largest.advance());
+
+    let assembly = face.glyph_assembly(radical, HBDirection::TTB);
+    let target = largest.advance() * 4;
+    let large = face.stretch_glyph(radical, HBDirection::TTB, target);
+    assert_eq!(large.is_assembly(), true);
+    assert_eq!(large.advance(), target);
+    assert_eq!(large.italics_correction(), assembly.italics_correction());
+
+    let parts = large.parts();
+    assert!(parts.len() > 3);
+    assert_eq!(parts[0].glyph_index(), assembly.parts()[0].glyph_index());
+    assert_eq!(parts[0].offset(), 0);
+    assert_eq!(parts[1].glyph_index(), assembly.parts()[1].glyph_index());
+    assert_eq!(parts[parts.len()-1].glyph_index(), assembly.parts()[2].glyph_index());
+
+    let min_overlap = face.min_connector_overlap_vertical();
+    for pair in parts.windows(2) {
+        let advance = assembly.parts().iter()
+            .find(|part| part.glyph_index() == pair[0].glyph_index()).unwrap().full_advance();
+        assert!(pair[0].offset() + advance - pair[1].offset() >= min_overlap);
+    }
+}
+
+#[test]
+fn it_shapes_text() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+
+    let glyphs = face.shape("abc", &ShapeOptions::new().direction(HBDirection::LTR)).unwrap();
+    assert_eq!(glyphs.len(), 3);
+    assert_eq!(glyphs[2].glyph_index(), 257);
+    assert_eq!(glyphs[0].cluster(), 0);
+    assert_eq!(glyphs[1].cluster(), 1);
+    assert_eq!(glyphs[2].cluster(), 2);
+
+    let glyphs = face.shape("Test", &ShapeOptions::new().direction(HBDirection::LTR)).unwrap();
+    let width: i32 = glyphs.iter().map(|glyph| glyph.x_advance()).sum();
+    assert_eq!(width, face.measure("Test", &ShapeOptions::new().direction(HBDirection::LTR)).unwrap().width());
+    assert!(!glyphs[0].is_unsafe_to_break());
+}
+
+#[test]
+fn it_shapes_text_with_options() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+
+    let options = ShapeOptions::new()
+        .direction(HBDirection::LTR)
+        .script("Latn")
+        .language("en")
+        .feature("kern=0".parse().unwrap());
+    let unkerned = face.measure("Test", &options).unwrap();
+    let guessed = face.measure("Test", &ShapeOptions::new()).unwrap();
+
+    assert!(unkerned.width() > guessed.width());
+    assert_eq!(guessed.width(), 1638);
+
+    // STIX2 kerns "AV" by -100 font units, -96 at 15 pixels per em
+    let kerned = face.measure("AV", &ShapeOptions::new()).unwrap();
+    let unkerned = face.measure("AV", &ShapeOptions::new().feature(Feature::new("kern", 0))).unwrap();
+    assert_eq!(unkerned.width() - kerned.width(), 96);
+
+    // ssty alternates are registered for the math script only
+    let options = ShapeOptions::new().script("Zmth");
+    assert_eq!(face.shape("x", &options).unwrap()[0].glyph_index(), 279);
+    let glyphs = face.shape("x", &options.feature(Feature::new("ssty", 1))).unwrap();
+    assert_eq!(glyphs.len(), 1);
+    assert_eq!(glyphs[0].glyph_index(), 4343);
+}
+
+#[test]
+fn it_reads_glyph_extents() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+
+    let x = face.glyph_extents(face.glyph_index('x' as u32).unwrap()).unwrap();
+    let g = face.glyph_extents(face.glyph_index('g' as u32).unwrap()).unwrap();
+    let big_t = face.glyph_extents(face.glyph_index('T' as u32).unwrap()).unwrap();
+    assert!(x.width() > 0);
+    assert!(x.height() < 0);
+    assert_eq!(x.ascent(), x.y_bearing());
+    assert_eq!(x.descent(), -(x.y_bearing() + x.height()));
+    assert!(g.descent() > 0);
+    assert!(big_t.ascent() > x.ascent());
+    assert_eq!(face.glyph_extents(909909), None);
+
+    let run = face.measure("Tgx", &ShapeOptions::new().direction(HBDirection::LTR)).unwrap();
+    let ink = run.ink_extents();
+    assert_eq!(ink.ascent(), big_t.ascent());
+    assert_eq!(ink.descent(), g.descent());
+    assert_eq!(ink.x_bearing(), big_t.x_bearing());
+    assert!(ink.width() > 0);
+
+    let empty = face.measure("", &ShapeOptions::new()).unwrap();
+    assert!(empty.ink_extents().is_empty());
+}
+
+#[test]
+fn it_measures_unicode_text() {
+    let face = open_ft_font("STIX2Math.otf") as FTFontRef;
+    let face = HBFace::from_freetype_font(face).unwrap();
+    let options = ShapeOptions::new().direction(HBDirection::LTR);
+
+    let empty = face.measure("", &options).unwrap();
+    assert_eq!(empty.width(), 0);
+    assert_eq!(empty.height(), 0);
+    assert_eq!(empty.positions().len(), 0);
+    assert_eq!(face.shape(&[0u16; 0][..], &options).unwrap().len(), 0);
+
+    let text = "∑𝐼√x";
+    let utf8 = face.shape(text, &options).unwrap();
+    assert_eq!(utf8.len(), 4);
+    assert_eq!(utf8[1].cluster(), 3);
+    assert_eq!(utf8[3].cluster(), 10);
+
+    let utf16: Vec<u16> = text.encode_utf16().collect();
+    let shaped = face.shape(&utf16[..], &options).unwrap();
+    assert_eq!(shaped.len(), 4);
+    assert_eq!(shaped[2].cluster(), 3);
+
+    let utf32: Vec<char> = text.chars().collect();
+    let shaped = face.shape(&utf32[..], &options).unwrap();
+    assert_eq!(shaped.len(), 4);
+    assert_eq!(shaped[3].cluster(), 3);
+
+    let width = face.measure(text, &options).unwrap().width();
+    assert_eq!(face.measure(&utf16[..], &options).unwrap().width(), width);
+    assert_eq!(face.measure(&utf32[..], &options).unwrap().width(), width);
+    assert_eq!(face.measure(&String::from(text), &options).unwrap().width(), width);
+
+    // Text is passed with its length, so interior NUL characters are shaped
+    assert_eq!(face.shape("a\0b", &options).unwrap().len(), 3);
+    assert_eq!(face.measure("a\0b", &options).unwrap().positions().len(), 3);
+}
+
 fn open_ft_font(name: &str) -> freetype_sys::FT_Face {
     unsafe {
         let mut library = ptr::null_mut();
@@ -0,0 +1,513 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+extern crate akriti_measure;
+
+use std::sync::Arc;
+
+use akriti_measure::error::Error;
+use akriti_measure::freetype::*;
+use akriti_measure::harfbuzz::{HBDirection, HBShapedGlyph};
+use akriti_measure::math::layout::*;
+use akriti_measure::math::style::*;
+use akriti_measure::shaping::*;
+
+static STIX2_MATH: &'static [u8] = include_bytes!("fonts/STIX2Math.otf");
+
+fn open_face() -> FreetypeFace {
+    let library = Arc::new(Freetype::new().unwrap());
+    FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap()
+}
+
+#[test]
+fn it_derives_styles() {
+    let display = MathStyle::display();
+    assert_eq!(display.numerator(), MathStyle::text());
+    assert_eq!(display.denominator(), MathStyle::text().cramped());
+    assert_eq!(MathStyle::text().numerator().script_level(), 1);
+    assert_eq!(MathStyle::script().cramped().numerator(), MathStyle::script_script().cramped());
+    assert_eq!(MathStyle::default(), MathStyle::text());
+}
+
+#[test]
+fn it_lays_out_display_fractions() {
+    let face = open_face();
+    let numerator = BoxMetrics::new(500, 700, 0);
+    let denominator = BoxMetrics::new(300, 700, 200);
+
+    let layout = fraction(face.get_hb_face(), &numerator, &denominator, MathStyle::display(), None);
+    assert_eq!(layout.numerator(), Point::new(0, 640));
+    assert_eq!(layout.denominator(), Point::new(100, -640));
+    assert_eq!(layout.rule(), Some(Rule::new(0, 224, 500, 68)));
+    assert_eq!(layout.metrics(), BoxMetrics::new(500, 1340, 840));
+}
+
+#[test]
+fn it_lays_out_text_fractions() {
+    let face = open_face();
+    let numerator = BoxMetrics::new(300, 700, 300);
+    let denominator = BoxMetrics::new(500, 400, 0);
+
+    let layout = fraction(face.get_hb_face(), &numerator, &denominator, MathStyle::text(), Some(100));
+    assert_eq!(layout.numerator(), Point::new(100, 676));
+    assert_eq!(layout.denominator(), Point::new(0, -585));
+    assert_eq!(layout.rule(), Some(Rule::new(0, 208, 500, 100)));
+    assert_eq!(layout.metrics(), BoxMetrics::new(500, 1376, 585));
+}
+
+#[test]
+fn it_lays_out_stacks() {
+    let face = open_face();
+    let numerator = BoxMetrics::new(400, 700, 100);
+    let denominator = BoxMetrics::new(400, 700, 0);
+
+    let layout = fraction(face.get_hb_face(), &numerator, &denominator, MathStyle::text(), Some(0));
+    assert_eq!(layout.rule(), None);
+    assert_eq!(layout.numerator(), Point::new(0, 517));
+    assert_eq!(layout.denominator(), Point::new(0, -433));
+    assert_eq!(layout.metrics(), BoxMetrics::new(400, 1217, 433));
+
+    let layout = fraction(face.get_hb_face(), &numerator, &BoxMetrics::new(400, 300, 0),
+                          MathStyle::display(), Some(0));
+    assert_eq!(layout.numerator(), Point::new(0, 780));
+    assert_eq!(layout.denominator(), Point::new(0, -690));
+}
+
+#[test]
+fn it_lays_out_square_roots() {
+    let face = open_face();
+    let base = BoxMetrics::new(500, 700, 200);
+
+    // The smallest √ variant, 1188 units tall, covers 700 + 200 + 85 + 68 and
+    // the extra 135 units are split between the gap and the descent
+    let layout = radical(face.get_hb_face(), &base, None, MathStyle::text()).unwrap();
+    let glyphs = layout.glyphs();
+    assert_eq!(glyphs.len(), 1);
+    assert_eq!((glyphs[0].glyph_index, glyphs[0].x), (1657, 0));
+    assert_eq!(layout.base(), Point::new(794, 0));
+    assert_eq!(layout.rule(), Rule::new(794, 852, 500, 68));
+    assert_eq!(layout.metrics(), BoxMetrics::new(1294, 988, 268));
+    assert_eq!(layout.degree(), None);
+
+    // Taller than the largest variant, so the assembly is stretched to exactly
+    // 3000 + 3000 + 170 + 68
+    let tall = BoxMetrics::new(500, 3000, 3000);
+    let layout = radical(face.get_hb_face(), &tall, None, MathStyle::display()).unwrap();
+    let glyphs: Vec<u32> = layout.glyphs().iter().map(|glyph| glyph.glyph_index).collect();
+    assert_eq!(glyphs, vec![1661, 1664, 1664, 1664, 1664, 1664, 1664, 1664, 1662]);
+    assert_eq!(layout.base(), Point::new(1212, 0));
+    assert_eq!(layout.rule(), Rule::new(1212, 3170, 500, 68));
+    assert_eq!(layout.metrics(), BoxMetrics::new(1712, 3306, 3000));
+}
+
+#[test]
+fn it_lays_out_nth_roots() {
+    let face = open_face();
+    let base = BoxMetrics::new(500, 700, 200);
+    let degree = BoxMetrics::new(300, 400, 0);
+
+    let layout = radical(face.get_hb_face(), &base, Some(&degree), MathStyle::text()).unwrap();
+    // kern before 65, kern after -335, raised by 55% of the 1188 unit sign
+    // from its bottom at -268
+    assert_eq!(layout.glyphs()[0].x, 30);
+    assert_eq!(layout.degree(), Some(Point::new(65, 385)));
+    assert_eq!(layout.base(), Point::new(824, 0));
+    assert_eq!(layout.metrics(), BoxMetrics::new(1324, 988, 268));
+}
+
+#[test]
+fn it_attaches_scripts() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    let base = BoxMetrics::new(500, 700, 0);
+    let superscript = BoxMetrics::new(300, 400, 0);
+    let layout = scripts(face, &base, None, None, Some(&superscript), MathStyle::text());
+    assert_eq!(layout.base(), Point::new(0, 0));
+    assert_eq!(layout.superscript(), Some(Point::new(500, 470)));
+    assert_eq!(layout.subscript(), None);
+    assert_eq!(layout.metrics(), BoxMetrics::new(840, 870, 0));
+
+    let base = BoxMetrics::new(500, 700, 200);
+    let subscript = BoxMetrics::new(300, 400, 100);
+    let layout = scripts(face, &base, None, Some(&subscript), None, MathStyle::text());
+    assert_eq!(layout.subscript(), Some(Point::new(500, -360)));
+    assert_eq!(layout.metrics(), BoxMetrics::new(840, 700, 460));
+
+    let base = BoxMetrics::new(500, 400, 0);
+    let superscript = BoxMetrics::new(300, 400, 200);
+    let subscript = BoxMetrics::new(300, 500, 100);
+    let layout = scripts(face, &base, None, Some(&subscript), Some(&superscript), MathStyle::text());
+    assert_eq!(layout.superscript(), Some(Point::new(500, 580)));
+    assert_eq!(layout.subscript(), Some(Point::new(500, -270)));
+
+    let layout = scripts(face, &base, None, None, Some(&superscript), MathStyle::text().cramped());
+    assert_eq!(layout.superscript(), Some(Point::new(500, 320)));
+}
+
+#[test]
+fn it_raises_superscripts_only_by_the_missing_gap() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    // The gap is 80 short of sub_superscript_gap_min while the superscript
+    // bottom could still rise by 220
+    let base = BoxMetrics::new(500, 400, 0);
+    let superscript = BoxMetrics::new(300, 300, 200);
+    let subscript = BoxMetrics::new(300, 300, 0);
+    let layout = scripts(face, &base, None, Some(&subscript), Some(&superscript), MathStyle::text());
+    let sub_shift = -layout.subscript().unwrap().y;
+    let sup_shift = layout.superscript().unwrap().y;
+    assert_eq!(sub_shift, face.subscript_shift_down());
+    assert_eq!(sup_shift, 440);
+    assert_eq!((sub_shift - subscript.ascent) + (sup_shift - superscript.descent), face.sub_superscript_gap_min());
+}
+
+#[test]
+fn it_kerns_scripts_into_the_base() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    // MATHEMATICAL ITALIC SMALL F
+    let glyph = face.glyph_index(0x1D453).unwrap();
+    let extents = face.glyph_extents(glyph).unwrap();
+    let base = BoxMetrics::new(500, extents.ascent(), extents.descent());
+    let script = BoxMetrics::new(300, 400, 100);
+
+    let layout = scripts(face, &base, Some(glyph), Some(&script), Some(&script), MathStyle::text());
+    let superscript = layout.superscript().unwrap();
+    let subscript = layout.subscript().unwrap();
+
+    let sup_kern = std::cmp::max(face.glyph_kerning_top_right(glyph, superscript.y - 100),
+                                 face.glyph_kerning_top_right(glyph, base.ascent));
+    let sub_kern = std::cmp::max(face.glyph_kerning_bottom_right(glyph, 400 + subscript.y),
+                                 face.glyph_kerning_bottom_right(glyph, -base.descent));
+    assert!(face.italics_correction(glyph) > 0);
+    assert_eq!(superscript.x, 500 + face.italics_correction(glyph) + sup_kern);
+    assert_eq!(subscript.x, 500 + sub_kern);
+}
+
+#[test]
+fn it_attaches_prescripts() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    let base = BoxMetrics::new(500, 700, 0);
+    let script = BoxMetrics::new(200, 300, 0);
+    let wide = BoxMetrics::new(300, 300, 0);
+    let layout = multiscripts(face, &base, None,
+                              &[ScriptPair::new(Some(&script), None)],
+                              &[ScriptPair::new(Some(&script), Some(&wide))],
+                              MathStyle::text());
+
+    assert_eq!(layout.base(), Point::new(340, 0));
+    let pre = layout.pre_scripts()[0];
+    assert_eq!(pre.subscript, Some(Point::new(140, -210)));
+    assert_eq!(pre.superscript.map(|point| point.x), Some(40));
+    assert_eq!(layout.subscript(), Some(Point::new(840, -210)));
+    assert_eq!(layout.metrics().width, 1080);
+}
+
+#[test]
+fn it_lays_out_limits() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    let base = BoxMetrics::new(1000, 800, 300);
+    let over = BoxMetrics::new(400, 300, 100);
+    let under = BoxMetrics::new(600, 300, 100);
+    let layout = under_over(face, UnderOverBox::new(&base), Some(UnderOverBox::new(&under)),
+                            Some(UnderOverBox::new(&over)), false, false);
+    assert_eq!(layout.base(), Point::new(0, 0));
+    assert_eq!(layout.over(), Some(Point::new(300, 1100)));
+    assert_eq!(layout.under(), Some(Point::new(200, -970)));
+    assert_eq!(layout.metrics(), BoxMetrics::new(1000, 1400, 1070));
+
+    let integral = UnderOverBox::new(&base).italics_correction(200);
+    let layout = under_over(face, integral, Some(UnderOverBox::new(&under)),
+                            Some(UnderOverBox::new(&over)), false, false);
+    assert_eq!(layout.over(), Some(Point::new(400, 1100)));
+    assert_eq!(layout.under(), Some(Point::new(100, -970)));
+}
+
+#[test]
+fn it_places_accents() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    let base = BoxMetrics::new(500, 700, 0);
+    let accent = BoxMetrics::new(200, 100, 0);
+    let layout = under_over(face, UnderOverBox::new(&base).top_accent_attachment(300), None,
+                            Some(UnderOverBox::new(&accent)), false, true);
+    // Raised by the part of the base above accent_base_height (480)
+    assert_eq!(layout.over(), Some(Point::new(200, 220)));
+    assert_eq!(layout.metrics(), BoxMetrics::new(500, 700, 0));
+
+    let wide = BoxMetrics::new(400, 100, 0);
+    let layout = under_over(face, UnderOverBox::new(&base).top_accent_attachment(0), None,
+                            Some(UnderOverBox::new(&wide)), false, true);
+    assert_eq!(layout.base(), Point::new(200, 0));
+    assert_eq!(layout.over(), Some(Point::new(0, 220)));
+    assert_eq!(layout.metrics().width, 700);
+
+    let short = BoxMetrics::new(500, 400, 0);
+    assert_eq!(accent_glyph(face, '\u{302}', &short), Ok(face.glyph_index(0x302).unwrap()));
+    // Bases taller than flattened_accent_base_height (656) take the flac form
+    assert_eq!(face.flattened_accent_base_height(), 656);
+    assert_eq!(accent_glyph(face, '\u{302}', &short), Ok(732));
+    assert_eq!(accent_glyph(face, '\u{302}', &BoxMetrics::new(500, 656, 0)), Ok(732));
+    assert_eq!(accent_glyph(face, '\u{302}', &BoxMetrics::new(500, 1000, 0)), Ok(4800));
+    assert_eq!(accent_glyph(face, '\u{4E2D}', &short), Err(Error::MissingGlyph('\u{4E2D}')));
+}
+
+#[test]
+fn it_stretches_horizontal_accents() {
+    let face = open_face();
+    let face = face.get_hb_face();
+
+    // TOP CURLY BRACKET
+    let brace = face.glyph_index(0x23DE).unwrap();
+    let stretched = stretch_horizontal(face, brace, 3000);
+    assert!(stretched.glyphs().len() > 1);
+    assert!(stretched.metrics().width >= 3000);
+    assert_eq!(stretched.glyphs()[0].x, 0);
+    assert!(stretched.glyphs().windows(2).all(|pair| pair[0].x < pair[1].x));
+}
+
+#[test]
+fn it_lays_out_overlines_and_underlines() {
+    let face = open_face();
+    let base = BoxMetrics::new(500, 700, 200);
+
+    let layout = overline(face.get_hb_face(), &base);
+    assert_eq!(layout.base(), Point::new(0, 0));
+    assert_eq!(layout.rule(), Rule::new(0, 875, 500, 68));
+    assert_eq!(layout.metrics(), BoxMetrics::new(500, 1011, 200));
+
+    let layout = underline(face.get_hb_face(), &base);
+    assert_eq!(layout.rule(), Rule::new(0, -443, 500, 68));
+    assert_eq!(layout.metrics(), BoxMetrics::new(500, 700, 511));
+
+    let mut face = face;
+    face.set_size_pixels(0, 20).unwrap();
+    let scaled = BoxMetrics::new(640, 896, 256);
+    let layout = overline(face.get_hb_face(), &scaled);
+    assert_eq!(layout.rule().y, 896 + face.get_hb_face().overbar_vertical_gap());
+    assert_eq!(layout.rule().height, face.get_hb_face().overbar_rule_thickness());
+}
+
+#[test]
+fn it_derives_fonts_for_script_styles() {
+    let mut face = open_face();
+    face.set_size_pixels(0, 20).unwrap();
+    let face = face.get_hb_face();
+
+    let text = face.font_for_style(MathStyle::text());
+    assert_eq!(text.scale(), 1280);
+    assert_eq!(text.script_level(), 0);
+
+    let script = face.font_for_style(MathStyle::display().superscript());
+    assert_eq!(script.scale(), 896);
+    assert_eq!(script.ppem(), Some(14.));
+    assert_eq!(script.script_level(), 1);
+
+    let script_script = face.font_for_style(MathStyle::script().subscript());
+    assert_eq!(script_script.scale(), 704);
+    assert_eq!(script_script.ppem(), Some(11.));
+    assert_eq!(script_script.script_level(), 2);
+
+    let text_prime = text.shape("x\u{2032}", &ShapeOptions::new()).unwrap();
+    let explicit = text.shape("x\u{2032}", &ShapeOptions::new().script("Zmth").feature(Feature::new("ssty", 1)))
+        .unwrap();
+    let automatic = script.shape("x\u{2032}", &ShapeOptions::new()).unwrap();
+    let disabled = script.shape("x\u{2032}", &ShapeOptions::new().feature(Feature::new("ssty", 0))).unwrap();
+    let glyphs = |glyphs: &Vec<HBShapedGlyph>| glyphs.iter().map(|glyph| glyph.glyph_index()).collect::<Vec<_>>();
+    assert_eq!(glyphs(&text_prime), vec![279, 1209]);
+    assert_eq!(glyphs(&automatic), vec![4343, 4515]);
+    assert_eq!(glyphs(&automatic), glyphs(&explicit));
+    assert_eq!(glyphs(&disabled), glyphs(&text_prime));
+
+    let script_script_prime = script_script.shape("x\u{2032}", &ShapeOptions::new()).unwrap();
+    assert_eq!(glyphs(&script_script_prime), vec![4592, 4764]);
+}
+
+#[test]
+fn it_maps_math_variants() {
+    use akriti_measure::math::variant::MathVariant;
+
+    assert_eq!(MathVariant::BoldItalic.apply('x'), '\u{1D499}');
+    assert_eq!(MathVariant::Italic.apply('h'), '\u{210E}');
+    assert_eq!(MathVariant::Script.apply('R'), '\u{211B}');
+    assert_eq!(MathVariant::Fraktur.apply('Z'), '\u{2128}');
+    assert_eq!(MathVariant::DoubleStruck.apply('N'), '\u{2115}');
+    assert_eq!(MathVariant::DoubleStruck.apply('7'), '\u{1D7DF}');
+    assert_eq!(MathVariant::Monospace.apply('a'), '\u{1D68A}');
+    assert_eq!(MathVariant::Bold.apply('\u{3B1}'), '\u{1D6C2}');
+    assert_eq!(MathVariant::Italic.apply('\u{2207}'), '\u{1D6FB}');
+    assert_eq!(MathVariant::Italic.apply('\u{131}'), '\u{1D6A4}');
+    assert_eq!(MathVariant::Initial.apply('\u{628}'), '\u{1EE21}');
+    assert_eq!(MathVariant::Looped.apply('\u{643}'), '\u{643}');
+    assert_eq!(MathVariant::Monospace.apply('\u{3B1}'), '\u{3B1}');
+    assert_eq!(MathVariant::Normal.apply('x'), 'x');
+
+    assert_eq!(MathVariant::split('\u{1D499}'), ('x', MathVariant::BoldItalic));
+    assert_eq!(MathVariant::split('\u{210E}'), ('h', MathVariant::Italic));
+    assert_eq!(MathVariant::split('\u{1D455}'), ('\u{1D455}', MathVariant::Normal));
+    assert_eq!(MathVariant::split('\u{1D7F6}'), ('0', MathVariant::Monospace));
+    assert_eq!(MathVariant::split('\u{1EEBB}'), ('\u{63A}', MathVariant::DoubleStruck));
+    assert_eq!(MathVariant::split('+'), ('+', MathVariant::Normal));
+
+    assert_eq!("sans-serif-bold-italic".parse(), Ok(MathVariant::SansSerifBoldItalic));
+    assert!("bold-oblique".parse::<MathVariant>().is_err());
+
+    let face = open_face();
+    let face = face.get_hb_face();
+    assert_eq!(face.glyph_index_with_variant('x', MathVariant::BoldItalic), face.glyph_index(0x1D499));
+    assert!(face.glyph_index_with_variant('h', MathVariant::Italic).is_some());
+}
+
+#[test]
+fn it_looks_up_operators() {
+    use akriti_measure::math::operators::*;
+
+    let paren = lookup('(', OperatorForm::Prefix).unwrap();
+    assert!(paren.is_fence() && paren.is_stretchy() && paren.is_symmetric());
+    assert_eq!(lookup('(', OperatorForm::Infix), None);
+    assert_eq!(operator_properties('(', OperatorForm::Infix).form(), OperatorForm::Prefix);
+
+    let plus = operator_properties('+', OperatorForm::Infix);
+    assert_eq!((plus.lspace(), plus.rspace()), (4, 4));
+    assert_eq!(operator_properties('+', OperatorForm::Prefix).lspace(), 0);
+
+    let sum = operator_properties('\u{2211}', OperatorForm::Prefix);
+    assert!(sum.is_large_op() && sum.has_movable_limits());
+    assert!(!operator_properties('\u{222B}', OperatorForm::Prefix).has_movable_limits());
+    assert!(operator_properties(',', OperatorForm::Infix).is_separator());
+    assert!(operator_properties('\u{23DE}', OperatorForm::Postfix).is_accent());
+
+    let unknown = operator_properties('x', OperatorForm::Infix);
+    assert_eq!((unknown.lspace(), unknown.rspace(), unknown.is_stretchy()), (5, 5, false));
+
+    // lookup binary searches the table
+    let entries: Vec<(char, OperatorForm)> = dictionary().map(|(ch, properties)| (ch, properties.form())).collect();
+    assert_eq!(entries.len(), 171);
+    assert!(entries.windows(2).all(|pair| pair[0] < pair[1]));
+    for (ch, properties) in dictionary() {
+        assert_eq!(lookup(ch, properties.form()), Some(properties));
+    }
+}
+
+#[test]
+fn it_picks_display_operators() {
+    use akriti_measure::math::operators::*;
+
+    let face = open_face();
+    let face = face.get_hb_face();
+    let sum = face.glyph_index(0x2211).unwrap();
+
+    let text = face.operator_glyph('\u{2211}', OperatorForm::Prefix, MathStyle::text()).unwrap();
+    assert_eq!(text.glyph_index(), sum);
+    assert_eq!(text.lspace(), 1000 / 18);
+
+    let display = face.operator_glyph('\u{2211}', OperatorForm::Prefix, MathStyle::display()).unwrap();
+    assert!(display.glyph_index() != sum);
+    let min_height = face.display_operator_min_height();
+    assert!(face.glyph_variants(sum, HBDirection::TTB)
+        .any(|variant| variant.glyph_index() == display.glyph_index() && variant.advance() >= min_height));
+
+    let plus = face.operator_glyph('+', OperatorForm::Infix, MathStyle::display()).unwrap();
+    assert_eq!(plus.glyph_index(), face.glyph_index('+' as u32).unwrap());
+    assert_eq!(plus.lspace(), 222);
+    assert!(!plus.is_extended_shape());
+    assert!(face.operator_glyph('(', OperatorForm::Prefix, MathStyle::text()).unwrap().is_extended_shape());
+}
+
+#[test]
+fn it_sizes_large_operators() {
+    let face = open_face();
+    let face = face.get_hb_face();
+    let integral = face.glyph_index(0x222B).unwrap();
+
+    let text = face.large_operator(integral, MathStyle::text());
+    assert_eq!(text.glyph_index(), integral);
+    assert_eq!(text.italics_correction(), face.italics_correction(integral));
+    assert_eq!(Some(text.extents()), face.glyph_extents(integral));
+
+    let display = face.large_operator(integral, MathStyle::display());
+    assert!(display.glyph_index() != integral);
+    assert!(display.extents().ascent() + display.extents().descent() > text.extents().ascent() + text.extents().descent());
+    assert_eq!(display.italics_correction(), face.italics_correction(display.glyph_index()));
+    assert!(display.italics_correction() > 0);
+
+    // Limits of the integral are moved apart by its italic correction
+    let base = BoxMetrics::new(display.extents().width(), display.extents().ascent(), display.extents().descent());
+    let limit = BoxMetrics::new(200, 300, 0);
+    let layout = under_over(face, UnderOverBox::new(&base).italics_correction(display.italics_correction()),
+                            Some(UnderOverBox::new(&limit)), Some(UnderOverBox::new(&limit)), false, false);
+    assert_eq!(layout.over().unwrap().x - layout.under().unwrap().x, display.italics_correction() / 2 * 2);
+}
+
+#[test]
+fn it_builds_math_box_trees() {
+    let face = open_face();
+    let face = face.get_hb_face();
+    let run = |text: &str| MathBox::run(face, &face.measure(text, &ShapeOptions::new()).unwrap());
+
+    let x = run("x");
+    assert_eq!(x.glyphs(), vec![PositionedGlyph::new(face.glyph_index('x' as u32).unwrap(), 0, 0, face.scale())]);
+    assert_eq!(x.top_accent_attachment(), face.top_accent_attachment(face.glyph_index('x' as u32).unwrap()));
+
+    let row = MathBox::row(vec![run("a"), run("+"), run("b")]);
+    assert_eq!(row.width(), run("a").width() + run("+").width() + run("b").width());
+    assert_eq!(row.glyphs().len(), 3);
+    assert_eq!(row.glyphs()[2].x, run("a").width() + run("+").width());
+
+    let numerator = run("a");
+    let denominator = run("b");
+    let layout = fraction(face, &numerator.metrics(), &denominator.metrics(), MathStyle::display(), None);
+    let frac = MathBox::fraction(face, numerator, denominator, MathStyle::display(), None);
+    assert_eq!(frac.metrics(), layout.metrics());
+    assert_eq!(frac.rules(), vec![layout.rule().unwrap()]);
+    assert_eq!(frac.glyphs()[0].y, layout.numerator().y);
+    assert_eq!(frac.glyphs()[1].y, layout.denominator().y);
+
+    let script = face.font_for_style(MathStyle::text().superscript());
+    let two = MathBox::run(&script, &script.measure("2", &ShapeOptions::new()).unwrap());
+    let layout = scripts(face, &x.metrics(), Some(x.glyphs()[0].glyph_index), None, Some(&two.metrics()),
+                         MathStyle::text());
+    let squared = MathBox::scripts(face, x.clone(), None, Some(two), MathStyle::text());
+    assert_eq!(squared.metrics(), layout.metrics());
+    let superscript = layout.superscript().unwrap();
+    assert_eq!((squared.glyphs()[1].x, squared.glyphs()[1].y), (superscript.x, superscript.y));
+    assert_eq!(squared.glyphs()[0].scale, face.scale());
+    assert_eq!(squared.glyphs()[1].scale, script.scale());
+
+    let root = MathBox::radical(face, x.clone(), None, MathStyle::text()).unwrap();
+    assert_eq!(root.rules().len(), 1);
+    assert!(root.glyphs().len() >= 2);
+
+    let paren = MathBox::stretched(face, face.glyph_index('(' as u32).unwrap(), HBDirection::TTB, 3000);
+    assert!(paren.ascent() + paren.descent() >= 3000);
+    assert!((paren.ascent() - paren.descent() - 2 * face.axis_height()).abs() <= 1);
+    match *paren.content() {
+        MathBoxContent::Stretched(ref glyphs) => assert!(glyphs.len() > 1),
+        _ => panic!("expected a stretched leaf"),
+    }
+
+    let hat = MathBox::under_over(face, x, None, Some(run("^")), false, true);
+    assert_eq!(hat.glyphs().len(), 2);
+}
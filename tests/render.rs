@@ -0,0 +1,124 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+extern crate akriti_measure;
+
+use std::sync::Arc;
+
+use akriti_measure::freetype::*;
+use akriti_measure::math::layout::MathBox;
+use akriti_measure::math::style::MathStyle;
+use akriti_measure::raster::RasterOptions;
+use akriti_measure::render::*;
+use akriti_measure::shaping::ShapeOptions;
+
+static STIX2_MATH: &'static [u8] = include_bytes!("fonts/STIX2Math.otf");
+
+fn open_face() -> FreetypeFace {
+    let library = Arc::new(Freetype::new().unwrap());
+    let mut face = FreetypeFace::new_from_memory(library, STIX2_MATH, 0).unwrap();
+    face.set_size_pixels(0, 20).unwrap();
+    face
+}
+
+#[test]
+fn it_fills_rectangles() {
+    let mut canvas = Canvas::new(4, 4, Color::white());
+    assert_eq!(canvas.data().len(), 64);
+    assert_eq!(canvas.pixel(3, 3), Color::white());
+
+    canvas.fill_rect(1., 1., 2., 2., Color::black());
+    assert_eq!(canvas.pixel(1, 1), Color::black());
+    assert_eq!(canvas.pixel(2, 2), Color::black());
+    assert_eq!(canvas.pixel(0, 0), Color::white());
+    assert_eq!(canvas.pixel(3, 1), Color::white());
+
+    canvas.fill_rect(0., 0., 0.5, 1., Color::black());
+    assert_eq!(canvas.pixel(0, 0), Color::new(128, 128, 128, 255));
+
+    let mut canvas = Canvas::new(2, 2, Color::transparent());
+    canvas.fill_rect(-5., -5., 10., 10., Color::new(255, 0, 0, 128));
+    assert_eq!(canvas.pixel(1, 1), Color::new(255, 0, 0, 128));
+}
+
+#[test]
+fn it_draws_shaped_runs() {
+    let face = open_face();
+    let positions = face.get_hb_face().measure("x+1", &ShapeOptions::new()).unwrap();
+
+    let mut canvas = Canvas::new(60, 40, Color::white());
+    canvas.draw_run(&face, &positions, 10.25, 30., Color::black(), &RasterOptions::new()).unwrap();
+
+    let mut inked = 0;
+    for y in 0..40 {
+        for x in 0..60 {
+            if canvas.pixel(x, y).r < 128 {
+                inked += 1;
+                assert!(x >= 9 && x < 10 + 1 + (positions.width() / 64) as u32);
+                assert!(y < 31);
+            }
+        }
+    }
+    assert!(inked > 20);
+    assert_eq!(canvas.pixel(0, 0), Color::white());
+}
+
+#[test]
+fn it_draws_math_boxes() {
+    let face = open_face();
+    let hb_face = face.get_hb_face();
+    let run = |text: &str| MathBox::run(hb_face, &hb_face.measure(text, &ShapeOptions::new()).unwrap());
+    let fraction = MathBox::fraction(hb_face, run("1"), run("2"), MathStyle::display(), None);
+
+    let mut canvas = Canvas::new(40, 80, Color::white());
+    canvas.draw_math_box(&face, &fraction, 5., 40., Color::black(), &RasterOptions::new()).unwrap();
+
+    // The fraction bar sits on the math axis, about 5 pixels above the baseline
+    let rule = fraction.rules()[0];
+    let bar_y = 40 - (rule.y + rule.height / 2) / 64;
+    assert!(canvas.pixel(5 + (rule.width / 128) as u32, bar_y as u32).r < 128);
+
+    let black = canvas.data().chunks(4).filter(|pixel| pixel[0] < 128).count();
+    assert!(black > 40);
+}
+
+#[test]
+fn it_draws_scripts_at_their_size() {
+    let face = open_face();
+    let hb_face = face.get_hb_face();
+    let script_face = hb_face.font_for_style(MathStyle::text().superscript());
+    let two = MathBox::run(&script_face, &script_face.measure("2", &ShapeOptions::new()).unwrap());
+    let full_two = MathBox::run(hb_face, &hb_face.measure("2", &ShapeOptions::new()).unwrap());
+    let x = MathBox::run(hb_face, &hb_face.measure("x", &ShapeOptions::new()).unwrap());
+    let two_width = two.width();
+    let squared = MathBox::scripts(hb_face, x, None, Some(two), MathStyle::text());
+
+    // Rows with ink right of `first_column`, the middle of the measured glyph
+    // so that the italic overhang of the base is left out
+    let ink_height = |math_box: &MathBox, first_column: u32| {
+        let mut canvas = Canvas::new(60, 60, Color::white());
+        canvas.draw_math_box(&face, math_box, 5., 40., Color::black(), &RasterOptions::new()).unwrap();
+        (0..60).filter(|&y| (first_column..60).any(|x| canvas.pixel(x, y).r < 128)).count()
+    };
+
+    let superscript_column = 5 + ((squared.glyphs()[1].x + two_width / 2) / 64) as u32;
+    let script_height = ink_height(&squared, superscript_column);
+    let full_height = ink_height(&full_two, 5 + (full_two.width() / 128) as u32);
+    assert!(script_height > 4);
+    assert!(script_height < full_height);
+    assert!(script_height as f32 <= full_height as f32 * 0.8);
+}
@@ -0,0 +1,61 @@
+/*
+ * Copyright 2017 Sreejith Krishnan R
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+
+extern crate akriti_measure;
+
+use std::u32;
+
+use akriti_measure::harfbuzz::HBDirection;
+use akriti_measure::shaping::*;
+use akriti_measure::error::Error;
+
+#[test]
+fn it_builds_tags() {
+    assert_eq!(tag("ssty"), 0x73737479);
+    assert_eq!(tag("cv1"), tag("cv1 "));
+}
+
+#[test]
+fn it_parses_features() {
+    assert_eq!("ssty=2".parse::<Feature>().unwrap(), Feature::new("ssty", 2));
+    assert_eq!("-liga".parse::<Feature>().unwrap(), Feature::new("liga", 0));
+    assert_eq!("smcp".parse::<Feature>().unwrap(), Feature::new("smcp", 1));
+    assert_eq!("kern[3:5]=0".parse::<Feature>().unwrap(), Feature::with_range("kern", 0, 3, 5));
+    assert_eq!("=2".parse::<Feature>().err(), Some(Error::InvalidFeature(String::from("=2"))));
+
+    let feature = Feature::new("tnum", 1);
+    assert_eq!(feature.start(), 0);
+    assert_eq!(feature.end(), u32::MAX);
+}
+
+#[test]
+fn it_builds_shape_options() {
+    let options = ShapeOptions::new()
+        .direction(HBDirection::RTL)
+        .script("Arab")
+        .language("ar")
+        .feature(Feature::new("liga", 0))
+        .features(&[Feature::new("kern", 1), Feature::new("tnum", 1)]);
+
+    assert_eq!(options.get_direction(), Some(HBDirection::RTL));
+    assert_eq!(options.get_script(), Some("Arab"));
+    assert_eq!(options.get_language(), Some("ar"));
+    assert_eq!(options.get_features().len(), 3);
+
+    assert_eq!(ShapeOptions::from(HBDirection::LTR).get_direction(), Some(HBDirection::LTR));
+    assert_eq!(ShapeOptions::new().get_direction(), None);
+}